@@ -0,0 +1,140 @@
+//! 位域字段的原始整数表示
+//!
+//! `#[serdeb(bit_width = N)]` 字段通过 [`EncodeBits`] / [`DecodeBits`] 与整数互相转换,
+//! 派生宏会为带 `#[repr(uN)]` 的枚举自动实现。
+
+use core::num::{NonZero, Wrapping};
+
+use crate::decode::char_from_u32;
+use crate::error::DecodeError;
+
+/// 编码时转换成位域中的整数
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` can not be used as a bit_width field",
+    note = "use an integer, bool or #[repr(uN)] enum, or convert with as = <integer type>"
+)]
+pub trait EncodeBits {
+    type Raw;
+
+    fn to_bits(&self) -> Self::Raw;
+}
+
+/// 从位域中解出的整数转换回来, 不是合法值时返回错误
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` can not be used as a bit_width field",
+    note = "use an integer, bool or #[repr(uN)] enum, or convert with as = <integer type>"
+)]
+pub trait DecodeBits: Sized {
+    type Raw;
+
+    fn from_bits(raw: Self::Raw) -> Result<Self, DecodeError>;
+}
+
+impl EncodeBits for bool {
+    type Raw = u8;
+
+    #[inline]
+    fn to_bits(&self) -> u8 {
+        *self as u8
+    }
+}
+
+impl DecodeBits for bool {
+    type Raw = u8;
+
+    #[inline]
+    fn from_bits(raw: u8) -> Result<Self, DecodeError> {
+        Ok(raw != 0)
+    }
+}
+
+macro_rules! impl_bits_for_int {
+    ($($t:ty),+ $(,)?) => {
+        $(
+            impl EncodeBits for $t {
+                type Raw = $t;
+
+                #[inline]
+                fn to_bits(&self) -> $t {
+                    *self
+                }
+            }
+
+            impl DecodeBits for $t {
+                type Raw = $t;
+
+                #[inline]
+                fn from_bits(raw: $t) -> Result<Self, DecodeError> {
+                    Ok(raw)
+                }
+            }
+        )+
+    };
+}
+
+impl_bits_for_int!(u8, i8, u16, i16, u32, i32, u64, i64, u128, i128);
+
+impl EncodeBits for char {
+    type Raw = u32;
+
+    #[inline]
+    fn to_bits(&self) -> u32 {
+        *self as u32
+    }
+}
+
+impl DecodeBits for char {
+    type Raw = u32;
+
+    #[inline]
+    fn from_bits(raw: u32) -> Result<Self, DecodeError> {
+        char_from_u32(raw)
+    }
+}
+
+macro_rules! impl_bits_for_non_zero {
+    ($($t:ty),+ $(,)?) => {
+        $(
+            impl EncodeBits for NonZero<$t> {
+                type Raw = $t;
+
+                #[inline]
+                fn to_bits(&self) -> $t {
+                    self.get()
+                }
+            }
+
+            impl DecodeBits for NonZero<$t> {
+                type Raw = $t;
+
+                #[inline]
+                fn from_bits(raw: $t) -> Result<Self, DecodeError> {
+                    NonZero::new(raw).ok_or(DecodeError::InvalidValue {
+                        name: concat!("NonZero<", stringify!($t), ">"),
+                        value: 0,
+                    })
+                }
+            }
+        )+
+    };
+}
+
+impl_bits_for_non_zero!(u8, i8, u16, i16, u32, i32, u64, i64, u128, i128);
+
+impl<T: EncodeBits> EncodeBits for Wrapping<T> {
+    type Raw = T::Raw;
+
+    #[inline]
+    fn to_bits(&self) -> T::Raw {
+        self.0.to_bits()
+    }
+}
+
+impl<T: DecodeBits> DecodeBits for Wrapping<T> {
+    type Raw = T::Raw;
+
+    #[inline]
+    fn from_bits(raw: T::Raw) -> Result<Self, DecodeError> {
+        T::from_bits(raw).map(Wrapping)
+    }
+}
@@ -0,0 +1,173 @@
+//! 从 `bytes::Buf` / `Bytes` 解码
+
+#[cfg(feature = "std")]
+use std::{io::IoSlice, vec::Vec};
+
+use bytes::{Buf, Bytes};
+
+use crate::error::DecodeError;
+use crate::{DecodeLen, DecodeSource};
+
+/// 解码成功后按读取的字节数推进 `buf`, 失败时不推进
+///
+/// 值位于第一个块内时直接在块上解码; 跨越多个块时把后续块拷贝到连续的缓冲区,
+/// 每次至少拷贝到 `needed` 提示的长度, 且至少翻倍, 重新解码的总长度与值的长度成正比
+#[cfg(feature = "std")]
+pub(crate) fn decode_from_buf<T, B, F>(buf: &mut B, decode: F) -> Result<T, DecodeError>
+where
+    B: Buf,
+    F: Fn(&[u8], &mut usize) -> Result<T, DecodeError>,
+{
+    let mut offset = 0;
+    let chunk = buf.chunk();
+    let mut needed = match decode(chunk, &mut offset) {
+        Ok(value) => {
+            buf.advance(offset);
+            return Ok(value);
+        }
+        Err(DecodeError::UnexpectedEof { needed }) if chunk.len() < buf.remaining() => needed,
+        Err(e) => return Err(e),
+    };
+
+    let result = {
+        let mut cursor = ChunkCursor::new(&*buf);
+        let mut scratch = Vec::new();
+        // 上次解码时看到的长度
+        let mut seen = chunk.len();
+        loop {
+            // 不知道还缺多少时 (例如查找分隔符) 拷贝全部剩余数据
+            let want = needed.map_or(usize::MAX, |needed| {
+                seen.saturating_add(needed).max(seen.saturating_mul(2))
+            });
+            cursor.copy_to(&mut scratch, want);
+            if scratch.len() <= seen {
+                break Err(DecodeError::UnexpectedEof { needed });
+            }
+            seen = scratch.len();
+
+            offset = 0;
+            match decode(&scratch, &mut offset) {
+                Err(DecodeError::UnexpectedEof { needed: next }) => needed = next,
+                result => break result,
+            }
+        }
+    };
+
+    if result.is_ok() {
+        buf.advance(offset);
+    }
+    result
+}
+
+/// 不推进 `Buf` 地依次读取它的块, 通过 `chunks_vectored` 按需查看更多的块
+#[cfg(feature = "std")]
+struct ChunkCursor<'a, B> {
+    buf: &'a B,
+    slices: Vec<IoSlice<'a>>,
+    count: usize,
+    /// 下一个要读取的块和块内位置
+    index: usize,
+    pos: usize,
+}
+
+#[cfg(feature = "std")]
+impl<'a, B: Buf> ChunkCursor<'a, B> {
+    fn new(buf: &'a B) -> Self {
+        Self {
+            buf,
+            slices: Vec::new(),
+            count: 0,
+            index: 0,
+            pos: 0,
+        }
+    }
+
+    /// 拷贝到 `dst` 的长度达到 `len` 或没有更多数据为止
+    fn copy_to(&mut self, dst: &mut Vec<u8>, len: usize) {
+        while dst.len() < len {
+            if self.index == self.count {
+                // 上次查看时块已经全部取出, 说明没有更多的块
+                if self.count < self.slices.len() {
+                    return;
+                }
+                self.slices
+                    .resize((self.count * 2).max(16), IoSlice::new(&[]));
+                self.count = self.buf.chunks_vectored(&mut self.slices);
+                if self.index == self.count {
+                    return;
+                }
+            }
+
+            let slice = &self.slices[self.index][self.pos..];
+            let n = slice.len().min(len - dst.len());
+            dst.extend_from_slice(&slice[..n]);
+            self.pos += n;
+            if self.pos == self.slices[self.index].len() {
+                self.index += 1;
+                self.pos = 0;
+            }
+        }
+    }
+}
+
+/// 从 `Bytes` 解码, 通过 [`DecodeSource`] 把 `buf` 传给 `Bytes` 字段零拷贝地切片
+pub(crate) fn decode_from_bytes<T, F>(buf: &mut Bytes, decode: F) -> Result<T, DecodeError>
+where
+    F: FnOnce(DecodeSource<'_>, &mut usize) -> Result<T, DecodeError>,
+{
+    let mut offset = 0;
+    let value = decode(DecodeSource::shared(buf), &mut offset)?;
+    buf.advance(offset);
+    Ok(value)
+}
+
+/// 带着 `Bytes` 源时零拷贝, 否则复制
+fn decode_bytes(
+    source: DecodeSource<'_>,
+    offset: &mut usize,
+    len: usize,
+) -> Result<Bytes, DecodeError> {
+    let end = offset
+        .checked_add(len)
+        .ok_or(DecodeError::UnexpectedEof { needed: None })?;
+    let available = source.as_slice().len();
+    if end > available {
+        return Err(DecodeError::UnexpectedEof {
+            needed: Some(end - available),
+        });
+    }
+
+    let bytes = source.slice(*offset..end);
+    *offset = end;
+    Ok(bytes)
+}
+
+impl DecodeLen for Bytes {
+    #[inline]
+    fn decode_len_be(buf: &[u8], offset: &mut usize, len: usize) -> Result<Self, DecodeError> {
+        decode_bytes(DecodeSource::new(buf), offset, len)
+    }
+
+    #[inline]
+    fn decode_len_le(buf: &[u8], offset: &mut usize, len: usize) -> Result<Self, DecodeError> {
+        decode_bytes(DecodeSource::new(buf), offset, len)
+    }
+
+    #[inline]
+    fn decode_len_source_be(
+        source: DecodeSource<'_>,
+        offset: &mut usize,
+        len: usize,
+    ) -> Result<Self, DecodeError> {
+        decode_bytes(source, offset, len)
+    }
+
+    #[inline]
+    fn decode_len_source_le(
+        source: DecodeSource<'_>,
+        offset: &mut usize,
+        len: usize,
+    ) -> Result<Self, DecodeError> {
+        decode_bytes(source, offset, len)
+    }
+}
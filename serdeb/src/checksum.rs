@@ -0,0 +1,68 @@
+//! 校验和算法
+//!
+//! 内置算法可以通过名字在派生宏中使用, 例如
+//! `#[serdeb(checksum = "crc16_modbus", over = "start..=payload")]`,
+//! 自定义算法实现 [`Checksum`] 后通过路径使用: `#[serdeb(checksum = MyChecksum)]`。
+
+/// 校验和算法, 结果按字段类型截断后写入
+pub trait Checksum {
+    fn checksum(data: &[u8]) -> u64;
+}
+
+/// CRC-16/MODBUS, poly 0x8005 (反射 0xA001), init 0xFFFF
+pub struct Crc16Modbus;
+
+impl Checksum for Crc16Modbus {
+    fn checksum(data: &[u8]) -> u64 {
+        let mut crc: u16 = 0xFFFF;
+        for byte in data {
+            crc ^= *byte as u16;
+            for _ in 0..8 {
+                if crc & 1 != 0 {
+                    crc = (crc >> 1) ^ 0xA001;
+                } else {
+                    crc >>= 1;
+                }
+            }
+        }
+        crc as u64
+    }
+}
+
+/// CRC-32 (IEEE 802.3), poly 0x04C11DB7 (反射 0xEDB88320)
+pub struct Crc32;
+
+impl Checksum for Crc32 {
+    fn checksum(data: &[u8]) -> u64 {
+        let mut crc: u32 = 0xFFFF_FFFF;
+        for byte in data {
+            crc ^= *byte as u32;
+            for _ in 0..8 {
+                if crc & 1 != 0 {
+                    crc = (crc >> 1) ^ 0xEDB8_8320;
+                } else {
+                    crc >>= 1;
+                }
+            }
+        }
+        (crc ^ 0xFFFF_FFFF) as u64
+    }
+}
+
+/// 逐字节异或
+pub struct Xor8;
+
+impl Checksum for Xor8 {
+    fn checksum(data: &[u8]) -> u64 {
+        data.iter().fold(0u8, |acc, byte| acc ^ byte) as u64
+    }
+}
+
+/// 逐字节累加, 取低 8 位
+pub struct Sum8;
+
+impl Checksum for Sum8 {
+    fn checksum(data: &[u8]) -> u64 {
+        data.iter().fold(0u8, |acc, byte| acc.wrapping_add(*byte)) as u64
+    }
+}
@@ -0,0 +1,286 @@
+//! `tokio_util::codec` 集成
+//!
+//! ```ignore
+//! let codec = SerdebCodec::<Frame>::length_prefixed(PrefixWidth::U16).max_frame_size(1024);
+//! let mut framed = tokio_util::codec::Framed::new(stream, codec);
+//! ```
+
+use core::marker::PhantomData;
+use std::vec::Vec;
+
+use bytes::{Buf, BytesMut};
+
+use crate::error::{CodecError, EncodeError};
+use crate::{Decode, Encode, Endian, Writer};
+
+/// 默认最大帧长 8 MiB
+pub const DEFAULT_MAX_FRAME_SIZE: usize = 8 * 1024 * 1024;
+
+/// 长度前缀的宽度
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrefixWidth {
+    U8,
+    U16,
+    U32,
+    U64,
+}
+
+impl PrefixWidth {
+    /// 前缀占用的字节数
+    pub const fn bytes(self) -> usize {
+        match self {
+            PrefixWidth::U8 => 1,
+            PrefixWidth::U16 => 2,
+            PrefixWidth::U32 => 4,
+            PrefixWidth::U64 => 8,
+        }
+    }
+}
+
+/// 分帧方式
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Framing {
+    /// 帧前带 `width` 宽的长度, 长度不含自身, 字节序与报文相同
+    LengthPrefix { width: PrefixWidth },
+    /// 帧以分隔符结尾, 分隔符不属于报文
+    Delimiter(Vec<u8>),
+    /// 每帧固定长度
+    Fixed(usize),
+}
+
+/// 按 [`Framing`] 分帧, 帧内容通过 `Decode`/`Encode` 编解码
+#[derive(Debug)]
+pub struct SerdebCodec<T> {
+    framing: Framing,
+    endian: Endian,
+    max_frame_size: usize,
+    /// 分隔符分帧时已经查找过的长度, 下次从这里继续
+    searched: usize,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for SerdebCodec<T> {
+    fn clone(&self) -> Self {
+        Self {
+            framing: self.framing.clone(),
+            endian: self.endian,
+            max_frame_size: self.max_frame_size,
+            searched: self.searched,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> SerdebCodec<T> {
+    /// 默认大端, 最大帧长 [`DEFAULT_MAX_FRAME_SIZE`]
+    ///
+    /// # Panics
+    ///
+    /// 分隔符为空时 panic
+    pub fn new(framing: Framing) -> Self {
+        if let Framing::Delimiter(delimiter) = &framing {
+            assert!(!delimiter.is_empty(), "delimiter must not be empty");
+        }
+
+        Self {
+            framing,
+            endian: Endian::Big,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+            searched: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn length_prefixed(width: PrefixWidth) -> Self {
+        Self::new(Framing::LengthPrefix { width })
+    }
+
+    /// # Panics
+    ///
+    /// 分隔符为空时 panic
+    pub fn delimited(delimiter: impl Into<Vec<u8>>) -> Self {
+        Self::new(Framing::Delimiter(delimiter.into()))
+    }
+
+    pub fn fixed(size: usize) -> Self {
+        Self::new(Framing::Fixed(size))
+    }
+
+    pub fn endian(mut self, endian: Endian) -> Self {
+        self.endian = endian;
+        self
+    }
+
+    /// 帧内容 (不含长度前缀和分隔符) 的最大长度
+    pub fn max_frame_size(mut self, max_frame_size: usize) -> Self {
+        self.max_frame_size = max_frame_size;
+        self
+    }
+
+    pub fn framing(&self) -> &Framing {
+        &self.framing
+    }
+
+    fn check_frame_size(&self, size: usize) -> Result<(), CodecError> {
+        if size > self.max_frame_size {
+            return Err(CodecError::FrameTooLarge {
+                size,
+                max: self.max_frame_size,
+            });
+        }
+        Ok(())
+    }
+
+    /// 取出下一帧的内容, 数据不足时返回 `None`
+    fn next_frame(&mut self, src: &mut BytesMut) -> Result<Option<BytesMut>, CodecError> {
+        match &self.framing {
+            Framing::LengthPrefix { width } => {
+                let width = width.bytes();
+                if src.len() < width {
+                    return Ok(None);
+                }
+
+                let mut prefix = [0u8; 8];
+                let len = match self.endian {
+                    Endian::Big => {
+                        prefix[8 - width..].copy_from_slice(&src[..width]);
+                        u64::from_be_bytes(prefix)
+                    }
+                    Endian::Little => {
+                        prefix[..width].copy_from_slice(&src[..width]);
+                        u64::from_le_bytes(prefix)
+                    }
+                };
+                let len = usize::try_from(len).unwrap_or(usize::MAX);
+                self.check_frame_size(len)?;
+
+                if src.len() - width < len {
+                    src.reserve(width + len - src.len());
+                    return Ok(None);
+                }
+
+                src.advance(width);
+                Ok(Some(src.split_to(len)))
+            }
+            Framing::Delimiter(delimiter) => {
+                // 分隔符可能跨越上次查找的结尾
+                let from = self
+                    .searched
+                    .saturating_sub(delimiter.len() - 1)
+                    .min(src.len());
+                let found = memchr::memmem::find(&src[from..], delimiter).map(|pos| from + pos);
+                let delimiter_len = delimiter.len();
+
+                match found {
+                    Some(pos) => {
+                        self.searched = 0;
+                        self.check_frame_size(pos)?;
+                        let frame = src.split_to(pos);
+                        src.advance(delimiter_len);
+                        Ok(Some(frame))
+                    }
+                    None => {
+                        self.searched = src.len();
+                        // 分隔符可能只收到一部分
+                        self.check_frame_size(src.len().saturating_sub(delimiter_len))?;
+                        Ok(None)
+                    }
+                }
+            }
+            Framing::Fixed(size) => {
+                self.check_frame_size(*size)?;
+                if src.len() < *size {
+                    src.reserve(*size - src.len());
+                    return Ok(None);
+                }
+                Ok(Some(src.split_to(*size)))
+            }
+        }
+    }
+}
+
+impl<T: Decode> tokio_util::codec::Decoder for SerdebCodec<T> {
+    type Item = T;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<T>, CodecError> {
+        let Some(frame) = self.next_frame(src)? else {
+            return Ok(None);
+        };
+
+        let mut offset = 0;
+        let item = match self.endian {
+            Endian::Big => T::decode_be(&frame, &mut offset)?,
+            Endian::Little => T::decode_le(&frame, &mut offset)?,
+        };
+        if offset < frame.len() {
+            return Err(CodecError::TrailingBytes {
+                extra: frame.len() - offset,
+            });
+        }
+        Ok(Some(item))
+    }
+}
+
+impl<T: Encode> tokio_util::codec::Encoder<T> for SerdebCodec<T> {
+    type Error = CodecError;
+
+    fn encode(&mut self, item: T, dst: &mut BytesMut) -> Result<(), CodecError> {
+        <Self as tokio_util::codec::Encoder<&T>>::encode(self, &item, dst)
+    }
+}
+
+impl<T: Encode> tokio_util::codec::Encoder<&T> for SerdebCodec<T> {
+    type Error = CodecError;
+
+    fn encode(&mut self, item: &T, dst: &mut BytesMut) -> Result<(), CodecError> {
+        let frame_start = dst.len();
+        let prefix_width = match self.framing {
+            Framing::LengthPrefix { width } => width.bytes(),
+            _ => 0,
+        };
+        dst.write_slice(&[0u8; 8][..prefix_width])?;
+
+        let start = dst.len();
+        let res = match self.endian {
+            Endian::Big => item.encode_be(dst),
+            Endian::Little => item.encode_le(dst),
+        };
+        let size = dst.len() - start;
+
+        let res = res.map_err(CodecError::from).and_then(|_| {
+            self.check_frame_size(size)?;
+            match &self.framing {
+                Framing::LengthPrefix { width } => {
+                    let width = width.bytes();
+                    let len = size as u64;
+                    if width < 8 && len >> (width * 8) != 0 {
+                        return Err(EncodeError::LengthOverflow {
+                            field: "frame",
+                            len: size,
+                        }
+                        .into());
+                    }
+                    let prefix = &mut dst[frame_start..start];
+                    match self.endian {
+                        Endian::Big => prefix.copy_from_slice(&len.to_be_bytes()[8 - width..]),
+                        Endian::Little => prefix.copy_from_slice(&len.to_le_bytes()[..width]),
+                    }
+                    Ok(())
+                }
+                Framing::Delimiter(delimiter) => Ok(dst.write_slice(delimiter)?),
+                Framing::Fixed(fixed) if size != *fixed => Err(CodecError::FrameSizeMismatch {
+                    size,
+                    expected: *fixed,
+                }),
+                Framing::Fixed(_) => Ok(()),
+            }
+        });
+
+        // 失败时丢弃写了一半的帧
+        if res.is_err() {
+            dst.truncate(frame_start);
+        }
+        res
+    }
+}
@@ -1,25 +1,113 @@
-use crate::{Decode, DecodeStr};
+#[cfg(feature = "alloc")]
+use alloc::{
+    borrow::{Cow, ToOwned},
+    boxed::Box,
+    collections::{BTreeMap, BTreeSet, LinkedList, VecDeque},
+    rc::Rc,
+    string::{String, ToString},
+    sync::Arc,
+    vec::Vec,
+};
+#[cfg(feature = "std")]
+use core::hash::{BuildHasher, Hash};
+#[cfg(feature = "alloc")]
+use core::ops::Range;
+use core::{marker::PhantomData, mem::MaybeUninit, num::NonZero, num::Wrapping};
+
+#[cfg(feature = "alloc")]
+use bytes::Bytes;
+#[cfg(feature = "std")]
+use std::collections::{HashMap, HashSet};
+
+use crate::{Decode, error::DecodeError};
+#[cfg(feature = "alloc")]
+use crate::{DecodeLen, DecodeStr};
+
+/// 解码的输入, 带着 `Bytes` 源时 `Bytes` 字段从中零拷贝地切片
+#[derive(Debug, Clone, Copy)]
+pub struct DecodeSource<'a> {
+    buf: &'a [u8],
+    #[cfg(feature = "alloc")]
+    bytes: Option<&'a Bytes>,
+}
+
+impl<'a> DecodeSource<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        Self {
+            buf,
+            #[cfg(feature = "alloc")]
+            bytes: None,
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn shared(bytes: &'a Bytes) -> Self {
+        Self {
+            buf: bytes,
+            bytes: Some(bytes),
+        }
+    }
+
+    pub fn as_slice(&self) -> &'a [u8] {
+        self.buf
+    }
+
+    /// 带着 `Bytes` 源时共享它的内存, 否则复制
+    ///
+    /// # Panics
+    ///
+    /// `range` 超出输入时 panic
+    #[cfg(feature = "alloc")]
+    pub fn slice(&self, range: Range<usize>) -> Bytes {
+        match self.bytes {
+            Some(bytes) => bytes.slice(range),
+            None => Bytes::copy_from_slice(&self.buf[range]),
+        }
+    }
+}
+
+/// `UnexpectedEof` 换成 `Incomplete`, 出错时 `offset` 恢复原位
+#[doc(hidden)]
+pub fn decode_partial<T, F>(buf: &[u8], offset: &mut usize, decode: F) -> Result<T, DecodeError>
+where
+    F: FnOnce(&[u8], &mut usize) -> Result<T, DecodeError>,
+{
+    let start = *offset;
+    decode(buf, offset).map_err(|e| {
+        *offset = start;
+        match e {
+            DecodeError::UnexpectedEof { needed } => DecodeError::Incomplete { needed },
+            e => e,
+        }
+    })
+}
 
 impl Decode for bool {
+    const MIN_SIZE: usize = 1;
+
     #[inline]
-    fn decode_be(buf: &[u8], offset: &mut usize) -> Result<Self, ()> {
-        let index = *offset;
-        *offset += 1;
-        if *offset < buf.len() {
-            Ok(buf[index] == 1)
-        } else {
-            Err(())
+    fn decode_be(buf: &[u8], offset: &mut usize) -> Result<Self, DecodeError> {
+        match buf.get(*offset) {
+            Some(v) => {
+                *offset += 1;
+                Ok(*v == 1)
+            }
+            None => Err(DecodeError::UnexpectedEof {
+                needed: Some(*offset + 1 - buf.len()),
+            }),
         }
     }
 
     #[inline]
-    fn decode_le(buf: &[u8], offset: &mut usize) -> Result<Self, ()> {
-        let index = *offset;
-        *offset += 1;
-        if *offset < buf.len() {
-            Ok(buf[index] == 1)
-        } else {
-            Err(())
+    fn decode_le(buf: &[u8], offset: &mut usize) -> Result<Self, DecodeError> {
+        match buf.get(*offset) {
+            Some(v) => {
+                *offset += 1;
+                Ok(*v == 1)
+            }
+            None => Err(DecodeError::UnexpectedEof {
+                needed: Some(*offset + 1 - buf.len()),
+            }),
         }
     }
 }
@@ -28,29 +116,29 @@ macro_rules! impl_decode_for_fixed_primitive_data {
     ($($t:ty),+ $(,)?) => {
         $(
             impl Decode for $t {
+                const MIN_SIZE: usize = size_of::<$t>();
+
                 #[inline]
-                fn decode_be(buf: &[u8], offset: &mut usize) -> Result<Self, ()> {
-                    use std::convert::TryInto;
-                    let size = std::mem::size_of::<$t>();
+                fn decode_be(buf: &[u8], offset: &mut usize) -> Result<Self, DecodeError> {
+                    let size = size_of::<$t>();
                     if *offset + size <= buf.len() {
                         let bytes: [u8; size_of::<$t>()] = buf[*offset..*offset + size].try_into().unwrap();
                         *offset += size;
                         Ok(<$t>::from_be_bytes(bytes))
                     } else {
-                        Err(())
+                        Err(DecodeError::UnexpectedEof { needed: Some(*offset + size - buf.len()) })
                     }
                 }
 
                 #[inline]
-                fn decode_le(buf: &[u8], offset: &mut usize) -> Result<Self, ()> {
-                    use std::convert::TryInto;
-                    let size = std::mem::size_of::<$t>();
+                fn decode_le(buf: &[u8], offset: &mut usize) -> Result<Self, DecodeError> {
+                    let size = size_of::<$t>();
                     if *offset + size <= buf.len() {
                         let bytes: [u8; size_of::<$t>()] = buf[*offset..*offset + size].try_into().unwrap();
                         *offset += size;
                         Ok(<$t>::from_le_bytes(bytes))
                     } else {
-                        Err(())
+                        Err(DecodeError::UnexpectedEof { needed: Some(*offset + size - buf.len()) })
                     }
                 }
             }
@@ -61,68 +149,293 @@ macro_rules! impl_decode_for_fixed_primitive_data {
 // 使用宏批量实现
 impl_decode_for_fixed_primitive_data!(u8, i8, u16, i16, u32, i32, u64, i64, u128, i128, f32, f64);
 
-impl<T: Decode + Default + Copy, const N: usize> Decode for [T; N] {
+/// 按 u32 解码, 不是合法的 Unicode 标量值时返回 `DecodeError::InvalidValue`
+impl Decode for char {
+    const MIN_SIZE: usize = 4;
+
     #[inline]
-    fn decode_be(buf: &[u8], offset: &mut usize) -> Result<Self, ()> {
-        let mut list = [T::default(); N];
-        let mut err_index = None;
+    fn decode_be(buf: &[u8], offset: &mut usize) -> Result<Self, DecodeError> {
+        u32::decode_be(buf, offset).and_then(char_from_u32)
+    }
+
+    #[inline]
+    fn decode_le(buf: &[u8], offset: &mut usize) -> Result<Self, DecodeError> {
+        u32::decode_le(buf, offset).and_then(char_from_u32)
+    }
+}
 
-        for i in 0..N {
-            let res = T::decode_be(buf, offset);
-            match res {
-                Ok(v) => list[i] = v,
-                Err(_) => {
-                    err_index.get_or_insert(i);
+pub(crate) fn char_from_u32(v: u32) -> Result<char, DecodeError> {
+    char::from_u32(v).ok_or(DecodeError::InvalidValue {
+        name: "char",
+        value: v as i128,
+    })
+}
+
+/// 解出 0 时返回 `DecodeError::InvalidValue`
+macro_rules! impl_decode_for_non_zero {
+    ($($t:ty),+ $(,)?) => {
+        $(
+            impl Decode for NonZero<$t> {
+                const MIN_SIZE: usize = size_of::<$t>();
+
+                #[inline]
+                fn decode_be(buf: &[u8], offset: &mut usize) -> Result<Self, DecodeError> {
+                    <$t>::decode_be(buf, offset).and_then(<Self as crate::DecodeBits>::from_bits)
+                }
+
+                #[inline]
+                fn decode_le(buf: &[u8], offset: &mut usize) -> Result<Self, DecodeError> {
+                    <$t>::decode_le(buf, offset).and_then(<Self as crate::DecodeBits>::from_bits)
                 }
             }
-        }
+        )+
+    };
+}
 
-        if let Some(_err_index) = err_index {
-            Err(())
-        } else {
-            Ok(list)
-        }
+impl_decode_for_non_zero!(u8, i8, u16, i16, u32, i32, u64, i64, u128, i128);
+
+impl<T: Decode> Decode for Wrapping<T> {
+    const MIN_SIZE: usize = T::MIN_SIZE;
+    const READS_TO_END: bool = T::READS_TO_END;
+
+    #[inline]
+    fn decode_be(buf: &[u8], offset: &mut usize) -> Result<Self, DecodeError> {
+        T::decode_be(buf, offset).map(Wrapping)
     }
 
     #[inline]
-    fn decode_le(buf: &[u8], offset: &mut usize) -> Result<Self, ()> {
-        let mut list = [T::default(); N];
-        let mut err_index = None;
+    fn decode_le(buf: &[u8], offset: &mut usize) -> Result<Self, DecodeError> {
+        T::decode_le(buf, offset).map(Wrapping)
+    }
 
-        for i in 0..N {
-            let res = T::decode_le(buf, offset);
-            match res {
-                Ok(v) => list[i] = v,
-                Err(_) => {
-                    err_index.get_or_insert(i);
-                }
+    #[inline]
+    fn decode_versioned_be(
+        buf: &[u8],
+        offset: &mut usize,
+        version: u32,
+    ) -> Result<Self, DecodeError> {
+        T::decode_versioned_be(buf, offset, version).map(Wrapping)
+    }
+
+    #[inline]
+    fn decode_versioned_le(
+        buf: &[u8],
+        offset: &mut usize,
+        version: u32,
+    ) -> Result<Self, DecodeError> {
+        T::decode_versioned_le(buf, offset, version).map(Wrapping)
+    }
+
+    #[inline]
+    fn decode_partial_be(buf: &[u8], offset: &mut usize) -> Result<Self, DecodeError> {
+        T::decode_partial_be(buf, offset).map(Wrapping)
+    }
+
+    #[inline]
+    fn decode_partial_le(buf: &[u8], offset: &mut usize) -> Result<Self, DecodeError> {
+        T::decode_partial_le(buf, offset).map(Wrapping)
+    }
+
+    #[inline]
+    fn decode_source_be(source: DecodeSource<'_>, offset: &mut usize) -> Result<Self, DecodeError> {
+        T::decode_source_be(source, offset).map(Wrapping)
+    }
+
+    #[inline]
+    fn decode_source_le(source: DecodeSource<'_>, offset: &mut usize) -> Result<Self, DecodeError> {
+        T::decode_source_le(source, offset).map(Wrapping)
+    }
+}
+
+/// 零长度, 不读取任何字节
+impl<T: ?Sized> Decode for PhantomData<T> {
+    #[inline]
+    fn decode_be(_buf: &[u8], _offset: &mut usize) -> Result<Self, DecodeError> {
+        Ok(PhantomData)
+    }
+
+    #[inline]
+    fn decode_le(_buf: &[u8], _offset: &mut usize) -> Result<Self, DecodeError> {
+        Ok(PhantomData)
+    }
+}
+
+impl Decode for () {
+    #[inline]
+    fn decode_be(_buf: &[u8], _offset: &mut usize) -> Result<Self, DecodeError> {
+        Ok(())
+    }
+
+    #[inline]
+    fn decode_le(_buf: &[u8], _offset: &mut usize) -> Result<Self, DecodeError> {
+        Ok(())
+    }
+}
+
+/// 逐个解码数组元素, 出错时释放已解码的元素
+#[doc(hidden)]
+pub fn decode_array<T, const N: usize>(
+    mut decode: impl FnMut() -> Result<T, DecodeError>,
+) -> Result<[T; N], DecodeError> {
+    struct Guard<T, const N: usize> {
+        array: [MaybeUninit<T>; N],
+        len: usize,
+    }
+
+    impl<T, const N: usize> Drop for Guard<T, N> {
+        fn drop(&mut self) {
+            for item in &mut self.array[..self.len] {
+                // SAFETY: 前 len 个元素已初始化
+                unsafe { item.assume_init_drop() };
             }
         }
+    }
 
-        if let Some(_err_index) = err_index {
-            Err(())
-        } else {
-            Ok(list)
-        }
+    let mut guard = Guard {
+        array: [const { MaybeUninit::uninit() }; N],
+        len: 0,
+    };
+
+    while guard.len < N {
+        guard.array[guard.len].write(decode()?);
+        guard.len += 1;
+    }
+
+    // 所有权转移给返回值
+    guard.len = 0;
+    // SAFETY: N 个元素均已初始化, `[MaybeUninit<T>; N]` 与 `[T; N]` 布局相同
+    Ok(unsafe { guard.array.as_ptr().cast::<[T; N]>().read() })
+}
+
+impl<T: Decode, const N: usize> Decode for [T; N] {
+    const MIN_SIZE: usize = T::MIN_SIZE.saturating_mul(N);
+
+    #[inline]
+    fn decode_be(buf: &[u8], offset: &mut usize) -> Result<Self, DecodeError> {
+        decode_array(|| T::decode_be(buf, offset))
+    }
+
+    #[inline]
+    fn decode_le(buf: &[u8], offset: &mut usize) -> Result<Self, DecodeError> {
+        decode_array(|| T::decode_le(buf, offset))
+    }
+
+    #[inline]
+    fn decode_versioned_be(
+        buf: &[u8],
+        offset: &mut usize,
+        version: u32,
+    ) -> Result<Self, DecodeError> {
+        decode_array(|| T::decode_versioned_be(buf, offset, version))
+    }
+
+    #[inline]
+    fn decode_versioned_le(
+        buf: &[u8],
+        offset: &mut usize,
+        version: u32,
+    ) -> Result<Self, DecodeError> {
+        decode_array(|| T::decode_versioned_le(buf, offset, version))
+    }
+
+    #[inline]
+    fn decode_partial_be(buf: &[u8], offset: &mut usize) -> Result<Self, DecodeError> {
+        decode_partial(buf, offset, |buf, offset| {
+            decode_array(|| T::decode_partial_be(buf, offset))
+        })
+    }
+
+    #[inline]
+    fn decode_partial_le(buf: &[u8], offset: &mut usize) -> Result<Self, DecodeError> {
+        decode_partial(buf, offset, |buf, offset| {
+            decode_array(|| T::decode_partial_le(buf, offset))
+        })
+    }
+
+    #[inline]
+    fn decode_source_be(source: DecodeSource<'_>, offset: &mut usize) -> Result<Self, DecodeError> {
+        decode_array(|| T::decode_source_be(source, offset))
+    }
+
+    #[inline]
+    fn decode_source_le(source: DecodeSource<'_>, offset: &mut usize) -> Result<Self, DecodeError> {
+        decode_array(|| T::decode_source_le(source, offset))
     }
 }
 
 macro_rules! impl_decode_for_tuples {
     ($($name:ident),+) => {
         impl<$( $name: Decode ),+> Decode for ( $( $name, )+ ) {
+            const MIN_SIZE: usize = 0 $( + <$name as Decode>::MIN_SIZE )+;
+            const READS_TO_END: bool = false $( || <$name as Decode>::READS_TO_END )+;
+
             #[inline]
-            fn decode_be(buf: &[u8], offset: &mut usize) -> Result<Self, ()> {
+            fn decode_be(buf: &[u8], offset: &mut usize) -> Result<Self, DecodeError> {
                 Ok((
                     $( <$name as Decode>::decode_be(buf, offset)?, )+
                 ))
             }
 
             #[inline]
-            fn decode_le(buf: &[u8], offset: &mut usize) -> Result<Self, ()> {
+            fn decode_le(buf: &[u8], offset: &mut usize) -> Result<Self, DecodeError> {
                 Ok((
                     $( <$name as Decode>::decode_le(buf, offset)?, )+
                 ))
             }
+
+            #[inline]
+            fn decode_versioned_be(
+                buf: &[u8],
+                offset: &mut usize,
+                version: u32,
+            ) -> Result<Self, DecodeError> {
+                Ok((
+                    $( <$name as Decode>::decode_versioned_be(buf, offset, version)?, )+
+                ))
+            }
+
+            #[inline]
+            fn decode_versioned_le(
+                buf: &[u8],
+                offset: &mut usize,
+                version: u32,
+            ) -> Result<Self, DecodeError> {
+                Ok((
+                    $( <$name as Decode>::decode_versioned_le(buf, offset, version)?, )+
+                ))
+            }
+
+            #[inline]
+            fn decode_partial_be(buf: &[u8], offset: &mut usize) -> Result<Self, DecodeError> {
+                decode_partial(buf, offset, |buf, offset| {
+                    Ok((
+                        $( <$name as Decode>::decode_partial_be(buf, offset)?, )+
+                    ))
+                })
+            }
+
+            #[inline]
+            fn decode_partial_le(buf: &[u8], offset: &mut usize) -> Result<Self, DecodeError> {
+                decode_partial(buf, offset, |buf, offset| {
+                    Ok((
+                        $( <$name as Decode>::decode_partial_le(buf, offset)?, )+
+                    ))
+                })
+            }
+
+            #[inline]
+            fn decode_source_be(source: DecodeSource<'_>, offset: &mut usize) -> Result<Self, DecodeError> {
+                Ok((
+                    $( <$name as Decode>::decode_source_be(source, offset)?, )+
+                ))
+            }
+
+            #[inline]
+            fn decode_source_le(source: DecodeSource<'_>, offset: &mut usize) -> Result<Self, DecodeError> {
+                Ok((
+                    $( <$name as Decode>::decode_source_le(source, offset)?, )+
+                ))
+            }
         }
     };
 }
@@ -140,9 +453,13 @@ impl_decode_for_tuples!(A, B, C, D, E, F, G, H, I, J);
 impl_decode_for_tuples!(A, B, C, D, E, F, G, H, I, J, K);
 impl_decode_for_tuples!(A, B, C, D, E, F, G, H, I, J, K, L);
 
-
+#[cfg(feature = "alloc")]
 impl DecodeStr for String {
-    fn decode_str(buf: &[u8], offset: &mut usize, delimiter: Option<&[u8]>) -> Result<Self, ()> {
+    fn decode_str(
+        buf: &[u8],
+        offset: &mut usize,
+        delimiter: Option<&[u8]>,
+    ) -> Result<Self, DecodeError> {
         match delimiter {
             None => Ok(String::from_utf8_lossy(&buf[*offset..]).to_string()),
             Some(delimiter) => match memchr::memmem::find(&buf[*offset..], delimiter) {
@@ -151,8 +468,227 @@ impl DecodeStr for String {
                     *offset += pos + delimiter.len();
                     Ok(v)
                 }
-                None => Err(()),
+                None => Err(DecodeError::UnexpectedEof { needed: None }),
             },
         }
     }
 }
+
+/// 解码 `len` 个元素并收集到集合中, 出错时立即返回
+///
+/// 数据不足时把还没解码的元素按每个至少 `min_size` 字节计入 `needed`
+#[cfg(feature = "alloc")]
+#[doc(hidden)]
+pub fn decode_seq<C: FromIterator<T>, T>(
+    len: usize,
+    min_size: usize,
+    mut decode: impl FnMut() -> Result<T, DecodeError>,
+) -> Result<C, DecodeError> {
+    (0..len)
+        .map(|i| {
+            decode().map_err(|e| match e {
+                DecodeError::UnexpectedEof {
+                    needed: Some(needed),
+                } => DecodeError::UnexpectedEof {
+                    needed: Some(
+                        (len - i - 1)
+                            .saturating_mul(min_size)
+                            .saturating_add(needed),
+                    ),
+                },
+                e => e,
+            })
+        })
+        .collect()
+}
+
+macro_rules! impl_decode_for_pointer {
+    ($($ptr:ident),+ $(,)?) => {
+        $(
+            #[cfg(feature = "alloc")]
+            impl<T: Decode> Decode for $ptr<T> {
+                const MIN_SIZE: usize = T::MIN_SIZE;
+                const READS_TO_END: bool = T::READS_TO_END;
+
+                #[inline]
+                fn decode_be(buf: &[u8], offset: &mut usize) -> Result<Self, DecodeError> {
+                    T::decode_be(buf, offset).map($ptr::new)
+                }
+
+                #[inline]
+                fn decode_le(buf: &[u8], offset: &mut usize) -> Result<Self, DecodeError> {
+                    T::decode_le(buf, offset).map($ptr::new)
+                }
+
+                #[inline]
+                fn decode_versioned_be(
+                    buf: &[u8],
+                    offset: &mut usize,
+                    version: u32,
+                ) -> Result<Self, DecodeError> {
+                    T::decode_versioned_be(buf, offset, version).map($ptr::new)
+                }
+
+                #[inline]
+                fn decode_versioned_le(
+                    buf: &[u8],
+                    offset: &mut usize,
+                    version: u32,
+                ) -> Result<Self, DecodeError> {
+                    T::decode_versioned_le(buf, offset, version).map($ptr::new)
+                }
+
+                #[inline]
+                fn decode_partial_be(buf: &[u8], offset: &mut usize) -> Result<Self, DecodeError> {
+                    T::decode_partial_be(buf, offset).map($ptr::new)
+                }
+
+                #[inline]
+                fn decode_partial_le(buf: &[u8], offset: &mut usize) -> Result<Self, DecodeError> {
+                    T::decode_partial_le(buf, offset).map($ptr::new)
+                }
+
+                #[inline]
+                fn decode_source_be(source: DecodeSource<'_>, offset: &mut usize) -> Result<Self, DecodeError> {
+                    T::decode_source_be(source, offset).map($ptr::new)
+                }
+
+                #[inline]
+                fn decode_source_le(source: DecodeSource<'_>, offset: &mut usize) -> Result<Self, DecodeError> {
+                    T::decode_source_le(source, offset).map($ptr::new)
+                }
+            }
+
+            #[cfg(feature = "alloc")]
+            impl DecodeStr for $ptr<str> {
+                #[inline]
+                fn decode_str(
+                    buf: &[u8],
+                    offset: &mut usize,
+                    delimiter: Option<&[u8]>,
+                ) -> Result<Self, DecodeError> {
+                    String::decode_str(buf, offset, delimiter).map(Into::into)
+                }
+            }
+        )+
+    };
+}
+
+impl_decode_for_pointer!(Box, Rc, Arc);
+
+#[cfg(feature = "alloc")]
+impl<B: ToOwned + ?Sized> Decode for Cow<'_, B>
+where
+    B::Owned: Decode,
+{
+    const MIN_SIZE: usize = B::Owned::MIN_SIZE;
+    const READS_TO_END: bool = B::Owned::READS_TO_END;
+
+    #[inline]
+    fn decode_be(buf: &[u8], offset: &mut usize) -> Result<Self, DecodeError> {
+        B::Owned::decode_be(buf, offset).map(Cow::Owned)
+    }
+
+    #[inline]
+    fn decode_le(buf: &[u8], offset: &mut usize) -> Result<Self, DecodeError> {
+        B::Owned::decode_le(buf, offset).map(Cow::Owned)
+    }
+
+    #[inline]
+    fn decode_versioned_be(
+        buf: &[u8],
+        offset: &mut usize,
+        version: u32,
+    ) -> Result<Self, DecodeError> {
+        B::Owned::decode_versioned_be(buf, offset, version).map(Cow::Owned)
+    }
+
+    #[inline]
+    fn decode_versioned_le(
+        buf: &[u8],
+        offset: &mut usize,
+        version: u32,
+    ) -> Result<Self, DecodeError> {
+        B::Owned::decode_versioned_le(buf, offset, version).map(Cow::Owned)
+    }
+
+    #[inline]
+    fn decode_partial_be(buf: &[u8], offset: &mut usize) -> Result<Self, DecodeError> {
+        B::Owned::decode_partial_be(buf, offset).map(Cow::Owned)
+    }
+
+    #[inline]
+    fn decode_partial_le(buf: &[u8], offset: &mut usize) -> Result<Self, DecodeError> {
+        B::Owned::decode_partial_le(buf, offset).map(Cow::Owned)
+    }
+
+    #[inline]
+    fn decode_source_be(source: DecodeSource<'_>, offset: &mut usize) -> Result<Self, DecodeError> {
+        B::Owned::decode_source_be(source, offset).map(Cow::Owned)
+    }
+
+    #[inline]
+    fn decode_source_le(source: DecodeSource<'_>, offset: &mut usize) -> Result<Self, DecodeError> {
+        B::Owned::decode_source_le(source, offset).map(Cow::Owned)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl DecodeStr for Cow<'_, str> {
+    #[inline]
+    fn decode_str(
+        buf: &[u8],
+        offset: &mut usize,
+        delimiter: Option<&[u8]>,
+    ) -> Result<Self, DecodeError> {
+        String::decode_str(buf, offset, delimiter).map(Cow::Owned)
+    }
+}
+
+/// 逐个解码 `len` 个元素后收集
+#[cfg(feature = "alloc")]
+macro_rules! impl_decode_len {
+    ($([$($generics:tt)*] $ty:ty => $elem:ty),+ $(,)?) => {
+        $(
+            impl<$($generics)*> DecodeLen for $ty {
+                #[inline]
+                fn decode_len_be(buf: &[u8], offset: &mut usize, len: usize) -> Result<Self, DecodeError> {
+                    decode_seq(len, <$elem as Decode>::MIN_SIZE, || <$elem as Decode>::decode_be(buf, offset))
+                }
+
+                #[inline]
+                fn decode_len_le(buf: &[u8], offset: &mut usize, len: usize) -> Result<Self, DecodeError> {
+                    decode_seq(len, <$elem as Decode>::MIN_SIZE, || <$elem as Decode>::decode_le(buf, offset))
+                }
+
+                #[inline]
+                fn decode_len_source_be(source: DecodeSource<'_>, offset: &mut usize, len: usize) -> Result<Self, DecodeError> {
+                    decode_seq(len, <$elem as Decode>::MIN_SIZE, || <$elem as Decode>::decode_source_be(source, offset))
+                }
+
+                #[inline]
+                fn decode_len_source_le(source: DecodeSource<'_>, offset: &mut usize, len: usize) -> Result<Self, DecodeError> {
+                    decode_seq(len, <$elem as Decode>::MIN_SIZE, || <$elem as Decode>::decode_source_le(source, offset))
+                }
+            }
+        )+
+    };
+}
+
+#[cfg(feature = "alloc")]
+impl_decode_len! {
+    [T: Decode] Vec<T> => T,
+    [T: Decode] VecDeque<T> => T,
+    [T: Decode] LinkedList<T> => T,
+    [T: Decode + Ord] BTreeSet<T> => T,
+    [K: Decode + Ord, V: Decode] BTreeMap<K, V> => (K, V),
+    [T: Decode] Box<[T]> => T,
+    [T: Decode] Rc<[T]> => T,
+    [T: Decode] Arc<[T]> => T,
+}
+
+#[cfg(feature = "std")]
+impl_decode_len! {
+    [T: Decode + Eq + Hash, S: BuildHasher + Default] HashSet<T, S> => T,
+    [K: Decode + Eq + Hash, V: Decode, S: BuildHasher + Default] HashMap<K, V, S> => (K, V),
+}
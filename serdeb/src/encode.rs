@@ -1,18 +1,28 @@
-use bytes::BufMut;
+#[cfg(feature = "alloc")]
+use alloc::{
+    borrow::{Cow, ToOwned},
+    boxed::Box,
+    collections::{BTreeMap, BTreeSet, LinkedList, VecDeque},
+    rc::Rc,
+    string::String,
+    sync::Arc,
+    vec::Vec,
+};
+use core::{marker::PhantomData, num::NonZero, num::Wrapping};
+#[cfg(feature = "std")]
+use std::collections::{HashMap, HashSet};
 
-use crate::{Encode, EncodeStr, error::EncodeError};
+use crate::{Encode, EncodeStr, Writer, error::EncodeError};
 
 impl Encode for bool {
     #[inline]
-    fn encode_be(&self, buf: &mut bytes::BytesMut) -> Result<(), EncodeError> {
-        buf.put_u8(*self as u8);
-        Ok(())
+    fn encode_be<W: Writer>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        buf.write_slice(&[*self as u8])
     }
 
     #[inline]
-    fn encode_le(&self, buf: &mut bytes::BytesMut) -> Result<(), EncodeError> {
-        buf.put_u8(*self as u8);
-        Ok(())
+    fn encode_le<W: Writer>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        buf.write_slice(&[*self as u8])
     }
 }
 
@@ -21,15 +31,13 @@ macro_rules! impl_encode_for_fixed_primitive_data {
         $(
             impl Encode for $t {
                 #[inline]
-                fn encode_be(&self, buf: &mut bytes::BytesMut) -> Result<(), EncodeError> {
-                    buf.extend_from_slice(&self.to_be_bytes());
-                    Ok(())
+                fn encode_be<W: Writer>(&self, buf: &mut W) -> Result<(), EncodeError> {
+                    buf.write_slice(&self.to_be_bytes())
                 }
 
                 #[inline]
-                fn encode_le(&self, buf: &mut bytes::BytesMut) -> Result<(), EncodeError> {
-                    buf.extend_from_slice(&self.to_le_bytes());
-                    Ok(())
+                fn encode_le<W: Writer>(&self, buf: &mut W) -> Result<(), EncodeError> {
+                    buf.write_slice(&self.to_le_bytes())
                 }
             }
         )+
@@ -38,9 +46,89 @@ macro_rules! impl_encode_for_fixed_primitive_data {
 
 impl_encode_for_fixed_primitive_data!(u8, i8, u16, i16, u32, i32, u64, i64, u128, i128, f32, f64);
 
+/// 按 u32 编码
+impl Encode for char {
+    #[inline]
+    fn encode_be<W: Writer>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        (*self as u32).encode_be(buf)
+    }
+
+    #[inline]
+    fn encode_le<W: Writer>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        (*self as u32).encode_le(buf)
+    }
+}
+
+macro_rules! impl_encode_for_non_zero {
+    ($($t:ty),+ $(,)?) => {
+        $(
+            impl Encode for NonZero<$t> {
+                #[inline]
+                fn encode_be<W: Writer>(&self, buf: &mut W) -> Result<(), EncodeError> {
+                    self.get().encode_be(buf)
+                }
+
+                #[inline]
+                fn encode_le<W: Writer>(&self, buf: &mut W) -> Result<(), EncodeError> {
+                    self.get().encode_le(buf)
+                }
+            }
+        )+
+    };
+}
+
+impl_encode_for_non_zero!(u8, i8, u16, i16, u32, i32, u64, i64, u128, i128);
+
+impl<T: Encode> Encode for Wrapping<T> {
+    #[inline]
+    fn encode_be<W: Writer>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        self.0.encode_be(buf)
+    }
+
+    #[inline]
+    fn encode_le<W: Writer>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        self.0.encode_le(buf)
+    }
+
+    #[inline]
+    fn encode_versioned_be<W: Writer>(&self, buf: &mut W, version: u32) -> Result<(), EncodeError> {
+        self.0.encode_versioned_be(buf, version)
+    }
+
+    #[inline]
+    fn encode_versioned_le<W: Writer>(&self, buf: &mut W, version: u32) -> Result<(), EncodeError> {
+        self.0.encode_versioned_le(buf, version)
+    }
+}
+
+/// 零长度, 不写入任何字节
+impl<T: ?Sized> Encode for PhantomData<T> {
+    #[inline]
+    fn encode_be<W: Writer>(&self, _buf: &mut W) -> Result<(), EncodeError> {
+        Ok(())
+    }
+
+    #[inline]
+    fn encode_le<W: Writer>(&self, _buf: &mut W) -> Result<(), EncodeError> {
+        Ok(())
+    }
+}
+
+impl Encode for () {
+    #[inline]
+    fn encode_be<W: Writer>(&self, _buf: &mut W) -> Result<(), EncodeError> {
+        Ok(())
+    }
+
+    #[inline]
+    fn encode_le<W: Writer>(&self, _buf: &mut W) -> Result<(), EncodeError> {
+        Ok(())
+    }
+}
+
 impl<T: Encode> Encode for [T] {
     #[inline]
-    fn encode_be(&self, buf: &mut bytes::BytesMut) -> Result<(), EncodeError> {
+    fn encode_be<W: Writer>(&self, buf: &mut W) -> Result<(), EncodeError> {
         for x in self {
             x.encode_be(buf)?;
         }
@@ -48,19 +136,93 @@ impl<T: Encode> Encode for [T] {
     }
 
     #[inline]
-    fn encode_le(&self, buf: &mut bytes::BytesMut) -> Result<(), EncodeError> {
+    fn encode_le<W: Writer>(&self, buf: &mut W) -> Result<(), EncodeError> {
         for x in self {
             x.encode_le(buf)?;
         }
         Ok(())
     }
+
+    #[inline]
+    fn encode_versioned_be<W: Writer>(&self, buf: &mut W, version: u32) -> Result<(), EncodeError> {
+        for x in self {
+            x.encode_versioned_be(buf, version)?;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn encode_versioned_le<W: Writer>(&self, buf: &mut W, version: u32) -> Result<(), EncodeError> {
+        for x in self {
+            x.encode_versioned_le(buf, version)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Encode, const N: usize> Encode for [T; N] {
+    #[inline]
+    fn encode_be<W: Writer>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        self.as_slice().encode_be(buf)
+    }
+
+    #[inline]
+    fn encode_le<W: Writer>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        self.as_slice().encode_le(buf)
+    }
+
+    #[inline]
+    fn encode_versioned_be<W: Writer>(&self, buf: &mut W, version: u32) -> Result<(), EncodeError> {
+        self.as_slice().encode_versioned_be(buf, version)
+    }
+
+    #[inline]
+    fn encode_versioned_le<W: Writer>(&self, buf: &mut W, version: u32) -> Result<(), EncodeError> {
+        self.as_slice().encode_versioned_le(buf, version)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: Encode> Encode for Vec<T> {
+    #[inline]
+    fn encode_be<W: Writer>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        self.as_slice().encode_be(buf)
+    }
+
+    #[inline]
+    fn encode_le<W: Writer>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        self.as_slice().encode_le(buf)
+    }
+
+    #[inline]
+    fn encode_versioned_be<W: Writer>(&self, buf: &mut W, version: u32) -> Result<(), EncodeError> {
+        self.as_slice().encode_versioned_be(buf, version)
+    }
+
+    #[inline]
+    fn encode_versioned_le<W: Writer>(&self, buf: &mut W, version: u32) -> Result<(), EncodeError> {
+        self.as_slice().encode_versioned_le(buf, version)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Encode for bytes::Bytes {
+    #[inline]
+    fn encode_be<W: Writer>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        buf.write_slice(self)
+    }
+
+    #[inline]
+    fn encode_le<W: Writer>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        buf.write_slice(self)
+    }
 }
 
 macro_rules! impl_encode_for_tuples {
     ($($name:ident),+) => {
         impl<$( $name: Encode ),+> Encode for ( $( $name, )+ ) {
             #[inline]
-            fn encode_be(&self, buf: &mut bytes::BytesMut) -> Result<(), EncodeError> {
+            fn encode_be<W: Writer>(&self, buf: &mut W) -> Result<(), EncodeError> {
                 #[allow(non_snake_case)]
                 let ( $( $name, )+ ) = self;
                 $( $name.encode_be(buf)?; )+
@@ -68,12 +230,37 @@ macro_rules! impl_encode_for_tuples {
             }
 
             #[inline]
-            fn encode_le(&self, buf: &mut bytes::BytesMut) -> Result<(), EncodeError> {
+            fn encode_le<W: Writer>(&self, buf: &mut W) -> Result<(), EncodeError> {
                 #[allow(non_snake_case)]
                 let ( $( $name, )+ ) = self;
                 $( $name.encode_le(buf)?; )+
                 Ok(())
             }
+
+            #[inline]
+            fn encode_versioned_be<W: Writer>(
+                &self,
+                buf: &mut W,
+                version: u32,
+            ) -> Result<(), EncodeError> {
+                #[allow(non_snake_case)]
+                let ( $( $name, )+ ) = self;
+                $( $name.encode_versioned_be(buf, version)?; )+
+                Ok(())
+            }
+
+            #[inline]
+            fn encode_versioned_le<W: Writer>(
+                &self,
+                buf: &mut W,
+                version: u32,
+            ) -> Result<(), EncodeError> {
+                #[allow(non_snake_case)]
+                let ( $( $name, )+ ) = self;
+                $( $name.encode_versioned_le(buf, version)?; )+
+                Ok(())
+            }
+
         }
     };
 }
@@ -93,10 +280,279 @@ impl_encode_for_tuples!(A, B, C, D, E, F, G, H, I, J, K, L);
 
 impl EncodeStr for str {
     #[inline]
-    fn encode_str(&self, buf: &mut bytes::BytesMut, delimiter: Option<&[u8]>) {
-        buf.extend_from_slice(self.as_bytes());
+    fn encode_str<W: Writer>(
+        &self,
+        buf: &mut W,
+        delimiter: Option<&[u8]>,
+    ) -> Result<(), EncodeError> {
+        buf.write_slice(self.as_bytes())?;
         if let Some(delimiter) = delimiter {
-            buf.extend_from_slice(delimiter);
+            buf.write_slice(delimiter)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl EncodeStr for String {
+    #[inline]
+    fn encode_str<W: Writer>(
+        &self,
+        buf: &mut W,
+        delimiter: Option<&[u8]>,
+    ) -> Result<(), EncodeError> {
+        self.as_str().encode_str(buf, delimiter)
+    }
+}
+
+macro_rules! impl_encode_for_pointer {
+    ($($ptr:ident),+ $(,)?) => {
+        $(
+            #[cfg(feature = "alloc")]
+            impl<T: Encode + ?Sized> Encode for $ptr<T> {
+                #[inline]
+                fn encode_be<W: Writer>(&self, buf: &mut W) -> Result<(), EncodeError> {
+                    (**self).encode_be(buf)
+                }
+
+                #[inline]
+                fn encode_le<W: Writer>(&self, buf: &mut W) -> Result<(), EncodeError> {
+                    (**self).encode_le(buf)
+                }
+
+                #[inline]
+                fn encode_versioned_be<W: Writer>(
+                    &self,
+                    buf: &mut W,
+                    version: u32,
+                ) -> Result<(), EncodeError> {
+                    (**self).encode_versioned_be(buf, version)
+                }
+
+                #[inline]
+                fn encode_versioned_le<W: Writer>(
+                    &self,
+                    buf: &mut W,
+                    version: u32,
+                ) -> Result<(), EncodeError> {
+                    (**self).encode_versioned_le(buf, version)
+                }
+            }
+
+            #[cfg(feature = "alloc")]
+            impl<T: EncodeStr + ?Sized> EncodeStr for $ptr<T> {
+                #[inline]
+                fn encode_str<W: Writer>(
+                    &self,
+                    buf: &mut W,
+                    delimiter: Option<&[u8]>,
+                ) -> Result<(), EncodeError> {
+                    (**self).encode_str(buf, delimiter)
+                }
+            }
+        )+
+    };
+}
+
+impl_encode_for_pointer!(Box, Rc, Arc);
+
+#[cfg(feature = "alloc")]
+impl<B: Encode + ToOwned + ?Sized> Encode for Cow<'_, B> {
+    #[inline]
+    fn encode_be<W: Writer>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        (**self).encode_be(buf)
+    }
+
+    #[inline]
+    fn encode_le<W: Writer>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        (**self).encode_le(buf)
+    }
+
+    #[inline]
+    fn encode_versioned_be<W: Writer>(&self, buf: &mut W, version: u32) -> Result<(), EncodeError> {
+        (**self).encode_versioned_be(buf, version)
+    }
+
+    #[inline]
+    fn encode_versioned_le<W: Writer>(&self, buf: &mut W, version: u32) -> Result<(), EncodeError> {
+        (**self).encode_versioned_le(buf, version)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<B: EncodeStr + ToOwned + ?Sized> EncodeStr for Cow<'_, B> {
+    #[inline]
+    fn encode_str<W: Writer>(
+        &self,
+        buf: &mut W,
+        delimiter: Option<&[u8]>,
+    ) -> Result<(), EncodeError> {
+        (**self).encode_str(buf, delimiter)
+    }
+}
+
+/// 只编码元素, 个数由 len_from/len_prefix 给出
+macro_rules! impl_encode_for_seq {
+    ($($seq:ident),+ $(,)?) => {
+        $(
+            #[cfg(feature = "alloc")]
+            impl<T: Encode> Encode for $seq<T> {
+                #[inline]
+                fn encode_be<W: Writer>(&self, buf: &mut W) -> Result<(), EncodeError> {
+                    for x in self {
+                        x.encode_be(buf)?;
+                    }
+                    Ok(())
+                }
+
+                #[inline]
+                fn encode_le<W: Writer>(&self, buf: &mut W) -> Result<(), EncodeError> {
+                    for x in self {
+                        x.encode_le(buf)?;
+                    }
+                    Ok(())
+                }
+
+                #[inline]
+                fn encode_versioned_be<W: Writer>(
+                    &self,
+                    buf: &mut W,
+                    version: u32,
+                ) -> Result<(), EncodeError> {
+                    for x in self {
+                        x.encode_versioned_be(buf, version)?;
+                    }
+                    Ok(())
+                }
+
+                #[inline]
+                fn encode_versioned_le<W: Writer>(
+                    &self,
+                    buf: &mut W,
+                    version: u32,
+                ) -> Result<(), EncodeError> {
+                    for x in self {
+                        x.encode_versioned_le(buf, version)?;
+                    }
+                    Ok(())
+                }
+            }
+        )+
+    };
+}
+
+impl_encode_for_seq!(VecDeque, LinkedList, BTreeSet);
+
+#[cfg(feature = "std")]
+impl<T: Encode, S> Encode for HashSet<T, S> {
+    #[inline]
+    fn encode_be<W: Writer>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        for x in self {
+            x.encode_be(buf)?;
         }
+        Ok(())
+    }
+
+    #[inline]
+    fn encode_le<W: Writer>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        for x in self {
+            x.encode_le(buf)?;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn encode_versioned_be<W: Writer>(&self, buf: &mut W, version: u32) -> Result<(), EncodeError> {
+        for x in self {
+            x.encode_versioned_be(buf, version)?;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn encode_versioned_le<W: Writer>(&self, buf: &mut W, version: u32) -> Result<(), EncodeError> {
+        for x in self {
+            x.encode_versioned_le(buf, version)?;
+        }
+        Ok(())
+    }
+}
+
+/// 映射按键值对依次编码
+#[cfg(feature = "alloc")]
+impl<K: Encode, V: Encode> Encode for BTreeMap<K, V> {
+    #[inline]
+    fn encode_be<W: Writer>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        for (k, v) in self {
+            k.encode_be(buf)?;
+            v.encode_be(buf)?;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn encode_le<W: Writer>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        for (k, v) in self {
+            k.encode_le(buf)?;
+            v.encode_le(buf)?;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn encode_versioned_be<W: Writer>(&self, buf: &mut W, version: u32) -> Result<(), EncodeError> {
+        for (k, v) in self {
+            k.encode_versioned_be(buf, version)?;
+            v.encode_versioned_be(buf, version)?;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn encode_versioned_le<W: Writer>(&self, buf: &mut W, version: u32) -> Result<(), EncodeError> {
+        for (k, v) in self {
+            k.encode_versioned_le(buf, version)?;
+            v.encode_versioned_le(buf, version)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K: Encode, V: Encode, S> Encode for HashMap<K, V, S> {
+    #[inline]
+    fn encode_be<W: Writer>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        for (k, v) in self {
+            k.encode_be(buf)?;
+            v.encode_be(buf)?;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn encode_le<W: Writer>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        for (k, v) in self {
+            k.encode_le(buf)?;
+            v.encode_le(buf)?;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn encode_versioned_be<W: Writer>(&self, buf: &mut W, version: u32) -> Result<(), EncodeError> {
+        for (k, v) in self {
+            k.encode_versioned_be(buf, version)?;
+            v.encode_versioned_be(buf, version)?;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn encode_versioned_le<W: Writer>(&self, buf: &mut W, version: u32) -> Result<(), EncodeError> {
+        for (k, v) in self {
+            k.encode_versioned_le(buf, version)?;
+            v.encode_versioned_le(buf, version)?;
+        }
+        Ok(())
     }
 }
@@ -3,10 +3,93 @@ use thiserror::Error;
 #[derive(Error, Debug)]
 pub enum EncodeError {
     #[error("Field: {field:?}, Value: {value:?} owerflow, please check bitwidth limit")]
-    BitWidthLimit { field: &'static str, value: String },
+    BitWidthLimit { field: &'static str, value: u128 },
+    #[error("Field: {field:?}, Length: {len:?} overflow the length field")]
+    LengthOverflow { field: &'static str, len: usize },
+    #[error("Field: {field:?}, Value: {value:?} out of the fixed-point range")]
+    FixedPointOutOfRange { field: &'static str, value: f64 },
+    /// `bytes = N` 字段或 `NarrowInt` 的值无法用 N 个字节表示
+    #[error("Value: {value:?} does not fit in {bytes:?} bytes")]
+    WidthOverflow { value: i128, bytes: usize },
+    /// `as = T` 字段的值超出线上类型的范围, 例如 `usize` 转 `u16`
+    #[error("Field: {field:?} can not be converted to the wire type")]
+    InvalidValue { field: &'static str },
+    /// `if` 条件成立时 `Option` 字段为 `None`, 或条件不成立时为 `Some`
+    #[error("Field: {field:?} is Some/None but its if condition disagrees")]
+    ConditionMismatch { field: &'static str },
+    #[error("Buffer full, {needed:?} more bytes needed")]
+    BufferFull { needed: usize },
+    #[error("The writer does not support back-patching computed fields")]
+    BackpatchUnsupported,
+    #[error("Placeholder at {pos:?} of size {size:?} is outside the {written:?} written bytes")]
+    PlaceholderOutOfBounds {
+        pos: usize,
+        size: usize,
+        written: usize,
+    },
 }
 
-#[derive(Error, Debug)]
+#[derive(Error, Debug, PartialEq, Eq)]
 pub enum DecodeError {
-    
-}
\ No newline at end of file
+    /// `needed` 为至少还需要的字节数, 无法确定时 (例如查找分隔符) 为 `None`
+    #[error("Unexpected end of buffer, {needed:?} more bytes needed")]
+    UnexpectedEof { needed: Option<usize> },
+    #[error("Varint overflows the target integer type")]
+    VarIntOverflow,
+    #[error("Varint is not minimally encoded")]
+    VarIntOverlong,
+    /// `decode_partial_*` 数据不足, 追加数据后可以重试
+    #[error("Incomplete input, {needed:?} more bytes needed")]
+    Incomplete { needed: Option<usize> },
+    #[error("Enum: {name:?}, Value: {value:?} is not a valid discriminant")]
+    InvalidDiscriminant { name: &'static str, value: i128 },
+    /// 线上值不是该类型的合法值, 例如 `as = T` 转换失败, `char` 超出范围或 `NonZero` 为 0
+    #[error("Type: {name:?}, Value: {value:?} can not be converted from the wire value")]
+    InvalidValue { name: &'static str, value: i128 },
+    #[error(
+        "Field: {field:?}, checksum mismatch, expected: {expected:#x}, computed: {computed:#x}"
+    )]
+    ChecksumMismatch {
+        field: &'static str,
+        expected: u64,
+        computed: u64,
+    },
+}
+
+/// 从 `std::io::Read` 解码的错误
+#[cfg(feature = "std")]
+#[derive(Error, Debug)]
+pub enum ReadError {
+    #[error(transparent)]
+    Decode(#[from] DecodeError),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// 编码到 `std::io::Write` 的错误
+#[cfg(feature = "std")]
+#[derive(Error, Debug)]
+pub enum WriteError {
+    #[error(transparent)]
+    Encode(#[from] EncodeError),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// `codec::SerdebCodec` 的错误
+#[cfg(feature = "tokio")]
+#[derive(Error, Debug)]
+pub enum CodecError {
+    #[error(transparent)]
+    Decode(#[from] DecodeError),
+    #[error(transparent)]
+    Encode(#[from] EncodeError),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("Frame size {size:?} exceeds the limit {max:?}")]
+    FrameTooLarge { size: usize, max: usize },
+    #[error("Frame size {size:?} does not match the fixed size {expected:?}")]
+    FrameSizeMismatch { size: usize, expected: usize },
+    #[error("Frame has {extra:?} trailing bytes after the message")]
+    TrailingBytes { extra: usize },
+}
@@ -0,0 +1,76 @@
+//! 定点数
+//!
+//! 线上传输整数 `raw`, 实际值为 `raw * scale + offset`,
+//! 字段上使用 `#[serdeb(as = u16, scale = 0.01, offset = -40.0)]`。
+
+use crate::error::EncodeError;
+
+/// 可以作为定点数原始值的整数
+pub trait FixedPoint: Copy {
+    /// 四舍五入, 超出范围或 NaN 时返回 `None`
+    fn from_f64(value: f64) -> Option<Self>;
+
+    fn to_f64(self) -> f64;
+}
+
+/// 四舍五入, 0.5 远离零, 与 `f64::round` 相同 (core 中没有 `f64::round`)
+fn round(value: f64) -> f64 {
+    // 2^52 以上的 f64 都是整数, NaN 和无穷原样返回
+    if value.is_nan() || value.abs() >= 4_503_599_627_370_496.0 {
+        return value;
+    }
+    let truncated = value as i64 as f64;
+    // 整数部分与 value 的指数相近, 相减没有舍入误差
+    let fraction = value - truncated;
+    if fraction >= 0.5 {
+        truncated + 1.0
+    } else if fraction <= -0.5 {
+        truncated - 1.0
+    } else {
+        truncated
+    }
+}
+
+macro_rules! impl_fixed_point {
+    ($($t:ty),+ $(,)?) => {
+        $(
+            impl FixedPoint for $t {
+                #[inline]
+                fn from_f64(value: f64) -> Option<Self> {
+                    let rounded = round(value);
+                    // MIN 可以精确表示; MAX + 1 是 2 的幂, 转换时 MAX 向上舍入到它或加 1 后等于它
+                    let in_range = rounded >= <$t>::MIN as f64 && rounded < <$t>::MAX as f64 + 1.0;
+                    // NaN 的比较都是 false
+                    if !in_range {
+                        return None;
+                    }
+                    Some(rounded as $t)
+                }
+
+                #[inline]
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+            }
+        )+
+    };
+}
+
+impl_fixed_point!(u8, i8, u16, i16, u32, i32, u64, i64, u128, i128);
+
+#[doc(hidden)]
+#[inline]
+pub fn to_fixed<R: FixedPoint>(
+    field: &'static str,
+    value: f64,
+    scale: f64,
+    offset: f64,
+) -> Result<R, EncodeError> {
+    R::from_f64((value - offset) / scale).ok_or(EncodeError::FixedPointOutOfRange { field, value })
+}
+
+#[doc(hidden)]
+#[inline]
+pub fn from_fixed<R: FixedPoint>(raw: R, scale: f64, offset: f64) -> f64 {
+    raw.to_f64() * scale + offset
+}
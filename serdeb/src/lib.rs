@@ -1,29 +1,286 @@
+#![no_std]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+mod bits;
+#[cfg(feature = "alloc")]
+mod buf;
+pub mod checksum;
+#[cfg(feature = "tokio")]
+pub mod codec;
 mod decode;
 mod encode;
 pub mod error;
+pub mod fixed;
+pub mod narrow;
+pub mod placeholder;
+mod size;
+#[cfg(feature = "std")]
+mod stream;
+#[cfg(feature = "alloc")]
+pub mod sync;
+pub mod varint;
+pub mod word;
+mod writer;
 
 #[cfg(feature = "derive")]
 pub use serdeb_derive::{Decoder, Encoder};
 
-pub use bytes::BytesMut;
+#[cfg(feature = "std")]
+use bytes::Buf;
+#[cfg(feature = "alloc")]
+pub use bytes::{Bytes, BytesMut};
 pub use memchr;
 
-use crate::error::EncodeError;
+use crate::error::{DecodeError, EncodeError};
+#[cfg(feature = "std")]
+use crate::error::{ReadError, WriteError};
+
+pub use crate::bits::{DecodeBits, EncodeBits};
+pub use crate::decode::DecodeSource;
+pub use crate::narrow::{I24, I40, I48, NarrowInt, U24, U40, U48};
+pub use crate::placeholder::{Placeholder, ReserveField};
+#[doc(hidden)]
+pub use crate::size::{add_fixed_size, mul_fixed_size};
+pub use crate::varint::{VarInt, ZigZag};
+pub use crate::word::WordSwapped;
+pub use crate::writer::{Backpatch, SliceWriter, Writer};
+
+/// 派生宏生成代码使用的路径, 不属于公开 API
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::decode::{decode_array, decode_partial};
+    pub use crate::fixed::{from_fixed, to_fixed};
+    pub use crate::writer::{SizeCounter, encoded_size_with, position};
+
+    #[cfg(feature = "alloc")]
+    pub use crate::decode::decode_seq;
+}
+
+/// 字节序
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endian {
+    Big,
+    Little,
+}
 
 pub trait Encode {
-    fn encode_be(&self, buf: &mut BytesMut) -> Result<(), EncodeError>;
-    fn encode_le(&self, buf: &mut BytesMut) -> Result<(), EncodeError>;
+    fn encode_be<W: Writer>(&self, buf: &mut W) -> Result<(), EncodeError>;
+    fn encode_le<W: Writer>(&self, buf: &mut W) -> Result<(), EncodeError>;
+
+    /// 按协商的协议版本编码, 不在 since/until 区间内的字段会被跳过
+    fn encode_versioned_be<W: Writer>(&self, buf: &mut W, version: u32) -> Result<(), EncodeError> {
+        let _ = version;
+        self.encode_be(buf)
+    }
+
+    fn encode_versioned_le<W: Writer>(&self, buf: &mut W, version: u32) -> Result<(), EncodeError> {
+        let _ = version;
+        self.encode_le(buf)
+    }
+
+    /// 按 encoded_size 一次性分配缓冲区后编码
+    #[cfg(feature = "alloc")]
+    fn to_bytes_be(&self) -> Result<BytesMut, EncodeError>
+    where
+        Self: EncodedSize,
+    {
+        let mut buf = BytesMut::with_capacity(self.try_encoded_size()?);
+        self.encode_be(&mut buf)?;
+        Ok(buf)
+    }
+
+    #[cfg(feature = "alloc")]
+    fn to_bytes_le(&self) -> Result<BytesMut, EncodeError>
+    where
+        Self: EncodedSize,
+    {
+        let mut buf = BytesMut::with_capacity(self.try_encoded_size()?);
+        self.encode_le(&mut buf)?;
+        Ok(buf)
+    }
+
+    /// 编码后写入 `writer`, 每次只缓冲当前这个值
+    #[cfg(feature = "std")]
+    fn encode_to_writer_be<W: std::io::Write + ?Sized>(
+        &self,
+        writer: &mut W,
+    ) -> Result<(), WriteError> {
+        stream::encode_to_writer(writer, |buf| self.encode_be(buf))
+    }
+
+    #[cfg(feature = "std")]
+    fn encode_to_writer_le<W: std::io::Write + ?Sized>(
+        &self,
+        writer: &mut W,
+    ) -> Result<(), WriteError> {
+        stream::encode_to_writer(writer, |buf| self.encode_le(buf))
+    }
+}
+
+/// 编码后的字节长度
+pub trait EncodedSize {
+    /// 所有值的编码长度都相同时为 `Some`, 可用于静态断言报文长度
+    const FIXED_SIZE: Option<usize> = None;
+
+    fn encoded_size(&self) -> usize;
+
+    /// 同 `encoded_size`, 自定义编码函数出错时返回它的错误
+    fn try_encoded_size(&self) -> Result<usize, EncodeError> {
+        Ok(self.encoded_size())
+    }
+}
+
+/// 可以作为 `len_prefix` 的长度类型
+pub trait LenPrefix: Sized {
+    /// 长度超出该类型的范围时返回 `None`
+    fn from_len(len: usize) -> Option<Self>;
+
+    /// 超出 `usize` 时饱和, 之后按数据不足处理
+    fn into_len(self) -> usize;
 }
 
 pub trait EncodeStr {
-    fn encode_str(&self, buf: &mut BytesMut, delimiter: Option<&[u8]>);
+    fn encode_str<W: Writer>(
+        &self,
+        buf: &mut W,
+        delimiter: Option<&[u8]>,
+    ) -> Result<(), EncodeError>;
 }
 
 pub trait Decode: Sized {
-    fn decode_be(buf: &[u8], offset: &mut usize) -> Result<Self, ()>;
-    fn decode_le(buf: &[u8], offset: &mut usize) -> Result<Self, ()>;
+    /// 编码至少占用的字节数, 数据不足时用来估计集合还需要的字节数
+    const MIN_SIZE: usize = 0;
+
+    /// 最后的字段取到输入的结尾 (没有分隔符的字符串), 从流中解码时要先读完整个流
+    const READS_TO_END: bool = false;
+
+    fn decode_be(buf: &[u8], offset: &mut usize) -> Result<Self, DecodeError>;
+    fn decode_le(buf: &[u8], offset: &mut usize) -> Result<Self, DecodeError>;
+
+    /// 按协商的协议版本解码, 不在 since/until 区间内的字段使用 default 填充
+    fn decode_versioned_be(
+        buf: &[u8],
+        offset: &mut usize,
+        version: u32,
+    ) -> Result<Self, DecodeError> {
+        let _ = version;
+        Self::decode_be(buf, offset)
+    }
+
+    fn decode_versioned_le(
+        buf: &[u8],
+        offset: &mut usize,
+        version: u32,
+    ) -> Result<Self, DecodeError> {
+        let _ = version;
+        Self::decode_le(buf, offset)
+    }
+
+    /// 用于分多次到达的数据, 不足时返回 `DecodeError::Incomplete`
+    ///
+    /// 出错时 `offset` 保持不变, 追加数据后可以从同一位置重试;
+    /// 数据用完时 `default` 字段不取默认值, 而是等待后续数据
+    fn decode_partial_be(buf: &[u8], offset: &mut usize) -> Result<Self, DecodeError> {
+        decode::decode_partial(buf, offset, Self::decode_be)
+    }
+
+    fn decode_partial_le(buf: &[u8], offset: &mut usize) -> Result<Self, DecodeError> {
+        decode::decode_partial(buf, offset, Self::decode_le)
+    }
+
+    /// 同 `decode_be`, `source` 带着 `Bytes` 源时其中的 `Bytes` 字段零拷贝地引用它
+    fn decode_source_be(source: DecodeSource<'_>, offset: &mut usize) -> Result<Self, DecodeError> {
+        Self::decode_be(source.as_slice(), offset)
+    }
+
+    fn decode_source_le(source: DecodeSource<'_>, offset: &mut usize) -> Result<Self, DecodeError> {
+        Self::decode_le(source.as_slice(), offset)
+    }
+
+    /// 从 `Buf` 解码并推进已读取的字节, 支持 `Buf::chain` 等不连续的缓冲区
+    ///
+    /// 查看第一个块之后的数据要用 `Buf::chunks_vectored`, 因此需要 `std`
+    #[cfg(feature = "std")]
+    fn decode_from_buf_be<B: Buf>(buf: &mut B) -> Result<Self, DecodeError> {
+        buf::decode_from_buf(buf, Self::decode_be)
+    }
+
+    #[cfg(feature = "std")]
+    fn decode_from_buf_le<B: Buf>(buf: &mut B) -> Result<Self, DecodeError> {
+        buf::decode_from_buf(buf, Self::decode_le)
+    }
+
+    /// 从 `Bytes` 解码, 其中的 `Bytes` 字段零拷贝地引用 `buf`
+    #[cfg(feature = "alloc")]
+    fn decode_from_bytes_be(buf: &mut Bytes) -> Result<Self, DecodeError> {
+        buf::decode_from_bytes(buf, Self::decode_source_be)
+    }
+
+    #[cfg(feature = "alloc")]
+    fn decode_from_bytes_le(buf: &mut Bytes) -> Result<Self, DecodeError> {
+        buf::decode_from_bytes(buf, Self::decode_source_le)
+    }
+
+    /// 从 `reader` 按需读取并解码, 读到结尾时返回 `DecodeError::UnexpectedEof`
+    ///
+    /// 只消费解码用到的字节, 其余的留在 `reader` 的缓冲区中,
+    /// 没有缓冲的 `Read` 可以包在 `std::io::BufReader` 中
+    #[cfg(feature = "std")]
+    fn decode_from_reader_be<R: std::io::BufRead + ?Sized>(
+        reader: &mut R,
+    ) -> Result<Self, ReadError> {
+        stream::decode_from_reader(
+            reader,
+            Self::READS_TO_END,
+            Self::decode_partial_be,
+            Self::decode_be,
+        )
+    }
+
+    #[cfg(feature = "std")]
+    fn decode_from_reader_le<R: std::io::BufRead + ?Sized>(
+        reader: &mut R,
+    ) -> Result<Self, ReadError> {
+        stream::decode_from_reader(
+            reader,
+            Self::READS_TO_END,
+            Self::decode_partial_le,
+            Self::decode_le,
+        )
+    }
 }
 
 pub trait DecodeStr: Sized {
-    fn decode_str(buf: &[u8], offset: &mut usize, delimiter: Option<&[u8]>) -> Result<Self, ()>;
-}
\ No newline at end of file
+    fn decode_str(
+        buf: &[u8],
+        offset: &mut usize,
+        delimiter: Option<&[u8]>,
+    ) -> Result<Self, DecodeError>;
+}
+
+/// 元素个数由 len_from/len_prefix 给出的集合
+pub trait DecodeLen: Sized {
+    fn decode_len_be(buf: &[u8], offset: &mut usize, len: usize) -> Result<Self, DecodeError>;
+    fn decode_len_le(buf: &[u8], offset: &mut usize, len: usize) -> Result<Self, DecodeError>;
+
+    /// 同 `decode_len_be`, 见 [`Decode::decode_source_be`]
+    fn decode_len_source_be(
+        source: DecodeSource<'_>,
+        offset: &mut usize,
+        len: usize,
+    ) -> Result<Self, DecodeError> {
+        Self::decode_len_be(source.as_slice(), offset, len)
+    }
+
+    fn decode_len_source_le(
+        source: DecodeSource<'_>,
+        offset: &mut usize,
+        len: usize,
+    ) -> Result<Self, DecodeError> {
+        Self::decode_len_le(source.as_slice(), offset, len)
+    }
+}
@@ -0,0 +1,140 @@
+//! 只占 N 个字节的整数
+//!
+//! 例如 24 位采样数据和 48 位计数器。[`NarrowInt`] 编码时检查取值范围,
+//! 有符号整数解码时做符号扩展。字段上可以用 `#[serdeb(bytes = 3)]`,
+//! 也可以直接使用 [`U24`] / [`I24`] / [`U48`] 等类型。
+
+use crate::error::{DecodeError, EncodeError};
+use crate::{Decode, Encode, EncodedSize, LenPrefix, Writer};
+
+/// 按低 `N` 个字节编码的整数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NarrowInt<T, const N: usize>(pub T);
+
+pub type U24 = NarrowInt<u32, 3>;
+pub type I24 = NarrowInt<i32, 3>;
+pub type U40 = NarrowInt<u64, 5>;
+pub type I40 = NarrowInt<i64, 5>;
+pub type U48 = NarrowInt<u64, 6>;
+pub type I48 = NarrowInt<i64, 6>;
+
+impl<T, const N: usize> NarrowInt<T, N> {
+    pub const fn new(v: T) -> Self {
+        Self(v)
+    }
+
+    pub fn get(self) -> T {
+        self.0
+    }
+}
+
+macro_rules! impl_narrow_int {
+    ($($t:ty),+ $(,)?) => {
+        $(
+            impl<const N: usize> NarrowInt<$t, N> {
+                const SHIFT: u32 = {
+                    assert!(N >= 1 && N <= size_of::<$t>(), "N must be in 1..=size_of::<T>()");
+                    ((size_of::<$t>() - N) * 8) as u32
+                };
+
+                /// 低 N 个字节能否表示该值, 有符号整数按符号扩展判断
+                fn check(self) -> Result<(), EncodeError> {
+                    if (self.0 << Self::SHIFT) >> Self::SHIFT == self.0 {
+                        Ok(())
+                    } else {
+                        Err(EncodeError::WidthOverflow { value: self.0 as i128, bytes: N })
+                    }
+                }
+            }
+
+            impl<const N: usize> Encode for NarrowInt<$t, N> {
+                #[inline]
+                fn encode_be<W: Writer>(&self, buf: &mut W) -> Result<(), EncodeError> {
+                    self.check()?;
+                    buf.write_slice(&self.0.to_be_bytes()[size_of::<$t>() - N..])
+                }
+
+                #[inline]
+                fn encode_le<W: Writer>(&self, buf: &mut W) -> Result<(), EncodeError> {
+                    self.check()?;
+                    buf.write_slice(&self.0.to_le_bytes()[..N])
+                }
+            }
+
+            impl<const N: usize> Decode for NarrowInt<$t, N> {
+                const MIN_SIZE: usize = N;
+
+                #[inline]
+                fn decode_be(buf: &[u8], offset: &mut usize) -> Result<Self, DecodeError> {
+                    let shift = Self::SHIFT;
+                    let src = read(buf, offset, N)?;
+                    let mut bytes = [0u8; size_of::<$t>()];
+                    bytes[size_of::<$t>() - N..].copy_from_slice(src);
+                    // 左移后算术右移完成符号扩展, 无符号整数不变
+                    Ok(Self((<$t>::from_be_bytes(bytes) << shift) >> shift))
+                }
+
+                #[inline]
+                fn decode_le(buf: &[u8], offset: &mut usize) -> Result<Self, DecodeError> {
+                    let shift = Self::SHIFT;
+                    let src = read(buf, offset, N)?;
+                    let mut bytes = [0u8; size_of::<$t>()];
+                    bytes[..N].copy_from_slice(src);
+                    Ok(Self((<$t>::from_le_bytes(bytes) << shift) >> shift))
+                }
+            }
+
+            impl<const N: usize> EncodedSize for NarrowInt<$t, N> {
+                const FIXED_SIZE: Option<usize> = Some(N);
+
+                #[inline]
+                fn encoded_size(&self) -> usize {
+                    N
+                }
+            }
+
+            impl<const N: usize> From<NarrowInt<$t, N>> for $t {
+                #[inline]
+                fn from(v: NarrowInt<$t, N>) -> Self {
+                    v.0
+                }
+            }
+        )+
+    };
+}
+
+impl_narrow_int!(u8, i8, u16, i16, u32, i32, u64, i64, u128, i128);
+
+/// 例如 TLS 握手消息的 24 位长度
+macro_rules! impl_len_prefix_for_narrow_int {
+    ($($t:ty),+ $(,)?) => {
+        $(
+            impl<const N: usize> LenPrefix for NarrowInt<$t, N> {
+                #[inline]
+                fn from_len(len: usize) -> Option<Self> {
+                    let v = NarrowInt(<$t>::from_len(len)?);
+                    v.check().ok().map(|_| v)
+                }
+
+                #[inline]
+                fn into_len(self) -> usize {
+                    self.0.into_len()
+                }
+            }
+        )+
+    };
+}
+
+impl_len_prefix_for_narrow_int!(u8, u16, u32, u64, u128);
+
+fn read<'a>(buf: &'a [u8], offset: &mut usize, len: usize) -> Result<&'a [u8], DecodeError> {
+    match buf.get(*offset..*offset + len) {
+        Some(src) => {
+            *offset += len;
+            Ok(src)
+        }
+        None => Err(DecodeError::UnexpectedEof {
+            needed: Some(*offset + len - buf.len()),
+        }),
+    }
+}
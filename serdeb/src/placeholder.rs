@@ -0,0 +1,84 @@
+use core::marker::PhantomData;
+
+use crate::writer::position;
+use crate::{Backpatch, Endian, Writer, error::EncodeError};
+
+/// 可以回填到预留位置的定长整数
+pub trait PlaceholderValue: Copy {
+    const SIZE: usize;
+
+    fn write(self, dst: &mut [u8], endian: Endian);
+}
+
+macro_rules! impl_placeholder_value_for_int {
+    ($($t:ty),+ $(,)?) => {
+        $(
+            impl PlaceholderValue for $t {
+                const SIZE: usize = size_of::<$t>();
+
+                #[inline]
+                fn write(self, dst: &mut [u8], endian: Endian) {
+                    match endian {
+                        Endian::Big => dst.copy_from_slice(&self.to_be_bytes()),
+                        Endian::Little => dst.copy_from_slice(&self.to_le_bytes()),
+                    }
+                }
+            }
+        )+
+    };
+}
+
+impl_placeholder_value_for_int!(u8, i8, u16, i16, u32, i32, u64, i64, u128, i128);
+
+/// 编码缓冲区中预留的字段, 在后续内容写入后回填
+#[must_use = "a reserved field must be filled"]
+#[derive(Debug)]
+pub struct Placeholder<T> {
+    pos: usize,
+    _marker: PhantomData<T>,
+}
+
+impl<T: PlaceholderValue> Placeholder<T> {
+    /// 预留位置在缓冲区中的起始下标
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// 预留位置不在 `buf` 已写入的范围内时 (例如换了一个缓冲区) 返回错误
+    pub fn fill<B: Backpatch + ?Sized>(
+        self,
+        buf: &mut B,
+        value: T,
+        endian: Endian,
+    ) -> Result<(), EncodeError> {
+        let written = buf.written_mut();
+        let len = written.len();
+        let dst = self
+            .pos
+            .checked_add(T::SIZE)
+            .and_then(|end| written.get_mut(self.pos..end))
+            .ok_or(EncodeError::PlaceholderOutOfBounds {
+                pos: self.pos,
+                size: T::SIZE,
+                written: len,
+            })?;
+        value.write(dst, endian);
+        Ok(())
+    }
+}
+
+pub trait ReserveField {
+    /// 写入 `T::SIZE` 个 0 字节占位, 输出不支持回填时返回 `EncodeError::BackpatchUnsupported`
+    fn reserve_field<T: PlaceholderValue>(&mut self) -> Result<Placeholder<T>, EncodeError>;
+}
+
+impl<W: Writer + ?Sized> ReserveField for W {
+    fn reserve_field<T: PlaceholderValue>(&mut self) -> Result<Placeholder<T>, EncodeError> {
+        let pos = position(self)?;
+        self.write_slice(&[0u8; 16][..T::SIZE])?;
+        Ok(Placeholder {
+            pos,
+            _marker: PhantomData,
+        })
+    }
+}
@@ -0,0 +1,296 @@
+#[cfg(feature = "alloc")]
+use alloc::{
+    borrow::{Cow, ToOwned},
+    boxed::Box,
+    collections::{BTreeMap, BTreeSet, LinkedList, VecDeque},
+    rc::Rc,
+    string::String,
+    sync::Arc,
+    vec::Vec,
+};
+use core::{marker::PhantomData, num::NonZero, num::Wrapping};
+#[cfg(feature = "std")]
+use std::collections::{HashMap, HashSet};
+
+use crate::{EncodedSize, LenPrefix};
+
+/// 两段定长相加, 任意一段不定长则结果不定长
+#[doc(hidden)]
+pub const fn add_fixed_size(a: Option<usize>, b: Option<usize>) -> Option<usize> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a + b),
+        _ => None,
+    }
+}
+
+#[doc(hidden)]
+pub const fn mul_fixed_size(a: Option<usize>, n: usize) -> Option<usize> {
+    match a {
+        Some(a) => Some(a * n),
+        None => None,
+    }
+}
+
+macro_rules! impl_encoded_size_for_fixed_primitive_data {
+    ($($t:ty),+ $(,)?) => {
+        $(
+            impl EncodedSize for $t {
+                const FIXED_SIZE: Option<usize> = Some(size_of::<$t>());
+
+                #[inline]
+                fn encoded_size(&self) -> usize {
+                    size_of::<$t>()
+                }
+            }
+        )+
+    };
+}
+
+impl_encoded_size_for_fixed_primitive_data!(
+    bool, u8, i8, u16, i16, u32, i32, u64, i64, u128, i128, f32, f64
+);
+
+impl EncodedSize for char {
+    const FIXED_SIZE: Option<usize> = Some(size_of::<u32>());
+
+    #[inline]
+    fn encoded_size(&self) -> usize {
+        size_of::<u32>()
+    }
+}
+
+macro_rules! impl_encoded_size_for_non_zero {
+    ($($t:ty),+ $(,)?) => {
+        $(
+            impl EncodedSize for NonZero<$t> {
+                const FIXED_SIZE: Option<usize> = Some(size_of::<$t>());
+
+                #[inline]
+                fn encoded_size(&self) -> usize {
+                    size_of::<$t>()
+                }
+            }
+        )+
+    };
+}
+
+impl_encoded_size_for_non_zero!(u8, i8, u16, i16, u32, i32, u64, i64, u128, i128);
+
+impl<T: EncodedSize> EncodedSize for Wrapping<T> {
+    const FIXED_SIZE: Option<usize> = T::FIXED_SIZE;
+
+    #[inline]
+    fn encoded_size(&self) -> usize {
+        self.0.encoded_size()
+    }
+}
+
+impl<T: ?Sized> EncodedSize for PhantomData<T> {
+    const FIXED_SIZE: Option<usize> = Some(0);
+
+    #[inline]
+    fn encoded_size(&self) -> usize {
+        0
+    }
+}
+
+impl EncodedSize for () {
+    const FIXED_SIZE: Option<usize> = Some(0);
+
+    #[inline]
+    fn encoded_size(&self) -> usize {
+        0
+    }
+}
+
+impl<T: EncodedSize, const N: usize> EncodedSize for [T; N] {
+    const FIXED_SIZE: Option<usize> = mul_fixed_size(T::FIXED_SIZE, N);
+
+    #[inline]
+    fn encoded_size(&self) -> usize {
+        match Self::FIXED_SIZE {
+            Some(size) => size,
+            None => self.iter().map(EncodedSize::encoded_size).sum(),
+        }
+    }
+}
+
+impl<T: EncodedSize> EncodedSize for [T] {
+    #[inline]
+    fn encoded_size(&self) -> usize {
+        match T::FIXED_SIZE {
+            Some(size) => size * self.len(),
+            None => self.iter().map(EncodedSize::encoded_size).sum(),
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: EncodedSize> EncodedSize for Vec<T> {
+    #[inline]
+    fn encoded_size(&self) -> usize {
+        self.as_slice().encoded_size()
+    }
+}
+
+/// 字符串的分隔符长度由派生宏另外计算
+impl EncodedSize for str {
+    #[inline]
+    fn encoded_size(&self) -> usize {
+        self.len()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl EncodedSize for bytes::Bytes {
+    #[inline]
+    fn encoded_size(&self) -> usize {
+        self.len()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl EncodedSize for String {
+    #[inline]
+    fn encoded_size(&self) -> usize {
+        self.len()
+    }
+}
+
+macro_rules! impl_len_prefix_for_unsigned {
+    ($($t:ty),+ $(,)?) => {
+        $(
+            impl LenPrefix for $t {
+                #[inline]
+                fn from_len(len: usize) -> Option<Self> {
+                    <$t>::try_from(len).ok()
+                }
+
+                #[inline]
+                fn into_len(self) -> usize {
+                    usize::try_from(self).unwrap_or(usize::MAX)
+                }
+            }
+        )+
+    };
+}
+
+impl_len_prefix_for_unsigned!(u8, u16, u32, u64, u128, usize);
+
+macro_rules! impl_encoded_size_for_tuples {
+    ($($name:ident),+) => {
+        impl<$( $name: EncodedSize ),+> EncodedSize for ( $( $name, )+ ) {
+            const FIXED_SIZE: Option<usize> = {
+                let size = Some(0);
+                $( let size = add_fixed_size(size, $name::FIXED_SIZE); )+
+                size
+            };
+
+            #[inline]
+            fn encoded_size(&self) -> usize {
+                #[allow(non_snake_case)]
+                let ( $( $name, )+ ) = self;
+                0 $( + $name.encoded_size() )+
+            }
+        }
+    };
+}
+
+impl_encoded_size_for_tuples!(A);
+impl_encoded_size_for_tuples!(A, B);
+impl_encoded_size_for_tuples!(A, B, C);
+impl_encoded_size_for_tuples!(A, B, C, D);
+impl_encoded_size_for_tuples!(A, B, C, D, E);
+impl_encoded_size_for_tuples!(A, B, C, D, E, F);
+impl_encoded_size_for_tuples!(A, B, C, D, E, F, G);
+impl_encoded_size_for_tuples!(A, B, C, D, E, F, G, H);
+impl_encoded_size_for_tuples!(A, B, C, D, E, F, G, H, I);
+impl_encoded_size_for_tuples!(A, B, C, D, E, F, G, H, I, J);
+impl_encoded_size_for_tuples!(A, B, C, D, E, F, G, H, I, J, K);
+impl_encoded_size_for_tuples!(A, B, C, D, E, F, G, H, I, J, K, L);
+
+macro_rules! impl_encoded_size_for_pointer {
+    ($($ptr:ident),+ $(,)?) => {
+        $(
+            #[cfg(feature = "alloc")]
+            impl<T: EncodedSize + ?Sized> EncodedSize for $ptr<T> {
+                const FIXED_SIZE: Option<usize> = T::FIXED_SIZE;
+
+                #[inline]
+                fn encoded_size(&self) -> usize {
+                    (**self).encoded_size()
+                }
+            }
+        )+
+    };
+}
+
+impl_encoded_size_for_pointer!(Box, Rc, Arc);
+
+#[cfg(feature = "alloc")]
+impl<B: EncodedSize + ToOwned + ?Sized> EncodedSize for Cow<'_, B> {
+    const FIXED_SIZE: Option<usize> = B::FIXED_SIZE;
+
+    #[inline]
+    fn encoded_size(&self) -> usize {
+        (**self).encoded_size()
+    }
+}
+
+macro_rules! impl_encoded_size_for_seq {
+    ($($seq:ident),+ $(,)?) => {
+        $(
+            #[cfg(feature = "alloc")]
+            impl<T: EncodedSize> EncodedSize for $seq<T> {
+                #[inline]
+                fn encoded_size(&self) -> usize {
+                    match T::FIXED_SIZE {
+                        Some(size) => size * self.len(),
+                        None => self.iter().map(EncodedSize::encoded_size).sum(),
+                    }
+                }
+            }
+        )+
+    };
+}
+
+impl_encoded_size_for_seq!(VecDeque, LinkedList, BTreeSet);
+
+#[cfg(feature = "std")]
+impl<T: EncodedSize, S> EncodedSize for HashSet<T, S> {
+    #[inline]
+    fn encoded_size(&self) -> usize {
+        match T::FIXED_SIZE {
+            Some(size) => size * self.len(),
+            None => self.iter().map(EncodedSize::encoded_size).sum(),
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<K: EncodedSize, V: EncodedSize> EncodedSize for BTreeMap<K, V> {
+    #[inline]
+    fn encoded_size(&self) -> usize {
+        match add_fixed_size(K::FIXED_SIZE, V::FIXED_SIZE) {
+            Some(size) => size * self.len(),
+            None => self
+                .iter()
+                .map(|(k, v)| k.encoded_size() + v.encoded_size())
+                .sum(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K: EncodedSize, V: EncodedSize, S> EncodedSize for HashMap<K, V, S> {
+    #[inline]
+    fn encoded_size(&self) -> usize {
+        match add_fixed_size(K::FIXED_SIZE, V::FIXED_SIZE) {
+            Some(size) => size * self.len(),
+            None => self
+                .iter()
+                .map(|(k, v)| k.encoded_size() + v.encoded_size())
+                .sum(),
+        }
+    }
+}
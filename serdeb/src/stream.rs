@@ -0,0 +1,77 @@
+//! 基于 `std::io::BufRead` / `std::io::Write` 的流式编解码
+
+use std::io::{BufRead, ErrorKind, Write};
+use std::vec::Vec;
+
+use crate::error::{DecodeError, EncodeError, ReadError, WriteError};
+
+/// 按 `reader` 缓冲区中的块读取, 只消费解码用到的字节, 不会多读属于下一条数据的内容
+///
+/// 流还没结束时用 `partial` 解码, 已读入的字节长度还不到 `needed` 提示的长度时不重新解码;
+/// 流结束后用 `decode` 解码剩下的数据, 这时末尾缺失的 `default` 字段才取默认值;
+/// `reads_to_end` 时 (没有分隔符的字符串) 先读完整个流再解码
+pub(crate) fn decode_from_reader<T, R, P, F>(
+    reader: &mut R,
+    reads_to_end: bool,
+    partial: P,
+    decode: F,
+) -> Result<T, ReadError>
+where
+    R: BufRead + ?Sized,
+    P: Fn(&[u8], &mut usize) -> Result<T, DecodeError>,
+    F: FnOnce(&[u8], &mut usize) -> Result<T, DecodeError>,
+{
+    let mut scratch = Vec::new();
+    if reads_to_end {
+        reader.read_to_end(&mut scratch)?;
+        return Ok(decode(&scratch, &mut 0)?);
+    }
+
+    // 至少要读到这个长度才值得重新解码
+    let mut wanted = 0;
+    loop {
+        let chunk = match reader.fill_buf() {
+            Ok(chunk) => chunk,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+        let start = scratch.len();
+        let len = chunk.len();
+        if len == 0 {
+            // 读到的字节都已消费, 不用再推进 `reader`
+            return Ok(decode(&scratch, &mut 0)?);
+        }
+        scratch.extend_from_slice(chunk);
+
+        if scratch.len() < wanted {
+            reader.consume(len);
+            continue;
+        }
+
+        let mut offset = 0;
+        match partial(&scratch, &mut offset) {
+            Ok(value) => {
+                // 之前的数据解码失败, 用到的字节一定延伸到这个块中
+                reader.consume(offset - start);
+                return Ok(value);
+            }
+            Err(DecodeError::Incomplete { needed }) => {
+                reader.consume(len);
+                wanted = scratch.len() + needed.unwrap_or(1);
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// 先编码到临时缓冲区, 回填完计算字段后再整体写出
+pub(crate) fn encode_to_writer<W, F>(writer: &mut W, encode: F) -> Result<(), WriteError>
+where
+    W: Write + ?Sized,
+    F: FnOnce(&mut Vec<u8>) -> Result<(), EncodeError>,
+{
+    let mut buf = Vec::new();
+    encode(&mut buf)?;
+    writer.write_all(&buf)?;
+    Ok(())
+}
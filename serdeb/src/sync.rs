@@ -0,0 +1,129 @@
+//! 噪声字节流中的帧同步
+//!
+//! 在接收到的数据中查找同步字 (帧头), 从同步字处尝试解码 `T`。
+//! 帧还没收完时等待后续数据, 只有长度超出上限或解码失败 (包括派生的校验和检查失败)
+//! 证明该帧无效时才跳到下一个同步字继续查找。
+
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use memchr::memmem;
+
+use crate::error::DecodeError;
+use crate::{Decode, Endian};
+
+/// 默认最大帧长, 超过时认为长度字段已损坏
+pub const DEFAULT_MAX_FRAME_SIZE: usize = 64 * 1024;
+
+/// 同步统计
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScanStats {
+    /// 成功解码的帧数
+    pub frames: u64,
+    /// 因不属于任何有效帧而丢弃的字节数
+    pub discarded_bytes: u64,
+    /// 同步字处解码失败的次数
+    pub bad_frames: u64,
+}
+
+/// 帧同步扫描器
+///
+/// 同步字是 `T` 编码的开头部分, 解码时包含在内
+#[derive(Debug)]
+pub struct FrameScanner<T> {
+    sync: Vec<u8>,
+    endian: Endian,
+    max_frame_size: usize,
+    buf: Vec<u8>,
+    stats: ScanStats,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Decode> FrameScanner<T> {
+    pub fn new(sync: impl Into<Vec<u8>>) -> Self {
+        let sync = sync.into();
+        assert!(!sync.is_empty(), "sync pattern must not be empty");
+
+        Self {
+            sync,
+            endian: Endian::Big,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+            buf: Vec::new(),
+            stats: ScanStats::default(),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn endian(mut self, endian: Endian) -> Self {
+        self.endian = endian;
+        self
+    }
+
+    pub fn max_frame_size(mut self, max_frame_size: usize) -> Self {
+        self.max_frame_size = max_frame_size;
+        self
+    }
+
+    /// 追加接收到的数据
+    pub fn push(&mut self, data: &[u8]) {
+        self.buf.extend_from_slice(data);
+    }
+
+    /// 取出下一帧, 数据不足时返回 `None`, 已缓冲的数据保留到下次
+    pub fn next_frame(&mut self) -> Option<T> {
+        loop {
+            let Some(pos) = memmem::find(&self.buf, &self.sync) else {
+                // 结尾可能是同步字的前一部分
+                let keep = (self.sync.len() - 1).min(self.buf.len());
+                self.discard(self.buf.len() - keep);
+                return None;
+            };
+            self.discard(pos);
+
+            match self.decode_head() {
+                Ok((frame, len)) => {
+                    self.buf.drain(..len);
+                    self.stats.frames += 1;
+                    return Some(frame);
+                }
+                // 后面的数据中出现的同步字可能只是这一帧的内容, 等它收完
+                Err(DecodeError::Incomplete { needed })
+                    if self.buf.len().saturating_add(needed.unwrap_or(1))
+                        <= self.max_frame_size =>
+                {
+                    return None;
+                }
+                Err(_) => {
+                    // 一次丢弃到下一个同步字
+                    self.stats.bad_frames += 1;
+                    let next = memmem::find(&self.buf[1..], &self.sync).map_or(1, |pos| pos + 1);
+                    self.discard(next);
+                }
+            }
+        }
+    }
+
+    /// 当前缓冲的字节数
+    pub fn buffered(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub fn stats(&self) -> ScanStats {
+        self.stats
+    }
+
+    /// 从缓冲区开头解码一帧, 返回帧和它的长度
+    fn decode_head(&self) -> Result<(T, usize), DecodeError> {
+        let mut offset = 0;
+        let frame = match self.endian {
+            Endian::Big => T::decode_partial_be(&self.buf, &mut offset)?,
+            Endian::Little => T::decode_partial_le(&self.buf, &mut offset)?,
+        };
+        Ok((frame, offset))
+    }
+
+    fn discard(&mut self, count: usize) {
+        self.buf.drain(..count);
+        self.stats.discarded_bytes += count as u64;
+    }
+}
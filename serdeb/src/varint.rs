@@ -0,0 +1,207 @@
+//! 变长整数
+//!
+//! [`VarInt`] 为无符号 LEB128 (protobuf 的 uint32/uint64),
+//! [`ZigZag`] 先做 zigzag 映射再按 LEB128 编码 (protobuf 的 sint32/sint64)。
+//! 字段上可以用 `#[serdeb(varint)]` / `#[serdeb(varint, zigzag)]`,
+//! 也可以作为长度前缀: `#[serdeb(len_prefix = VarInt<u32>)]`。
+
+use crate::error::{DecodeError, EncodeError};
+use crate::{Decode, Encode, EncodedSize, LenPrefix, Writer};
+
+/// 无符号 LEB128
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct VarInt<T>(pub T);
+
+/// zigzag + LEB128, 绝对值小的负数也只占很少字节
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ZigZag<T>(pub T);
+
+/// 可以按 LEB128 编码的无符号整数
+pub trait Unsigned: Copy {
+    const BITS: u32;
+
+    fn to_u128(self) -> u128;
+
+    /// 调用方保证 `value` 不超过 `BITS` 位
+    fn from_u128(value: u128) -> Self;
+}
+
+/// 可以按 zigzag 编码的有符号整数
+pub trait Signed: Copy {
+    type Unsigned: Unsigned;
+
+    fn zigzag(self) -> Self::Unsigned;
+
+    fn unzigzag(value: Self::Unsigned) -> Self;
+}
+
+macro_rules! impl_varint_for_int {
+    ($($u:ty => $i:ty),+ $(,)?) => {
+        $(
+            impl Unsigned for $u {
+                const BITS: u32 = <$u>::BITS;
+
+                #[inline]
+                fn to_u128(self) -> u128 {
+                    self as u128
+                }
+
+                #[inline]
+                fn from_u128(value: u128) -> Self {
+                    value as $u
+                }
+            }
+
+            impl Signed for $i {
+                type Unsigned = $u;
+
+                #[inline]
+                fn zigzag(self) -> $u {
+                    ((self << 1) ^ (self >> (<$i>::BITS - 1))) as $u
+                }
+
+                #[inline]
+                fn unzigzag(value: $u) -> Self {
+                    ((value >> 1) as $i) ^ -((value & 1) as $i)
+                }
+            }
+        )+
+    };
+}
+
+impl_varint_for_int!(u8 => i8, u16 => i16, u32 => i32, u64 => i64, u128 => i128, usize => isize);
+
+/// LEB128 编码的最大字节数
+const MAX_BYTES: usize = u128::BITS.div_ceil(7) as usize;
+
+fn encode_leb128<W: Writer>(mut value: u128, buf: &mut W) -> Result<(), EncodeError> {
+    let mut bytes = [0u8; MAX_BYTES];
+    let mut len = 0;
+
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes[len] = byte;
+            len += 1;
+            break;
+        }
+        bytes[len] = byte | 0x80;
+        len += 1;
+    }
+
+    buf.write_slice(&bytes[..len])
+}
+
+fn leb128_size(value: u128) -> usize {
+    ((u128::BITS - value.leading_zeros()).max(1)).div_ceil(7) as usize
+}
+
+/// 拒绝超出 `bits` 位的值和带多余 0 字节的非最短编码
+fn decode_leb128(buf: &[u8], offset: &mut usize, bits: u32) -> Result<u128, DecodeError> {
+    let max_bytes = bits.div_ceil(7) as usize;
+    let mut value = 0u128;
+
+    for i in 0..max_bytes {
+        let Some(&byte) = buf.get(*offset + i) else {
+            return Err(DecodeError::UnexpectedEof {
+                needed: Some(*offset + i + 1 - buf.len()),
+            });
+        };
+
+        let payload = (byte & 0x7F) as u128;
+        let shift = 7 * i as u32;
+        if shift + 7 > bits && payload >> (bits - shift) != 0 {
+            return Err(DecodeError::VarIntOverflow);
+        }
+        value |= payload << shift;
+
+        if byte & 0x80 == 0 {
+            if byte == 0 && i > 0 {
+                return Err(DecodeError::VarIntOverlong);
+            }
+            *offset += i + 1;
+            return Ok(value);
+        }
+    }
+
+    Err(DecodeError::VarIntOverflow)
+}
+
+impl<T: Unsigned> Encode for VarInt<T> {
+    #[inline]
+    fn encode_be<W: Writer>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        encode_leb128(self.0.to_u128(), buf)
+    }
+
+    #[inline]
+    fn encode_le<W: Writer>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        encode_leb128(self.0.to_u128(), buf)
+    }
+}
+
+impl<T: Unsigned> Decode for VarInt<T> {
+    const MIN_SIZE: usize = 1;
+
+    #[inline]
+    fn decode_be(buf: &[u8], offset: &mut usize) -> Result<Self, DecodeError> {
+        decode_leb128(buf, offset, T::BITS).map(|v| VarInt(T::from_u128(v)))
+    }
+
+    #[inline]
+    fn decode_le(buf: &[u8], offset: &mut usize) -> Result<Self, DecodeError> {
+        Self::decode_be(buf, offset)
+    }
+}
+
+impl<T: Unsigned> EncodedSize for VarInt<T> {
+    #[inline]
+    fn encoded_size(&self) -> usize {
+        leb128_size(self.0.to_u128())
+    }
+}
+
+impl<T: Unsigned + LenPrefix> LenPrefix for VarInt<T> {
+    #[inline]
+    fn from_len(len: usize) -> Option<Self> {
+        T::from_len(len).map(VarInt)
+    }
+
+    #[inline]
+    fn into_len(self) -> usize {
+        self.0.into_len()
+    }
+}
+
+impl<T: Signed> Encode for ZigZag<T> {
+    #[inline]
+    fn encode_be<W: Writer>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        VarInt(self.0.zigzag()).encode_be(buf)
+    }
+
+    #[inline]
+    fn encode_le<W: Writer>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        VarInt(self.0.zigzag()).encode_le(buf)
+    }
+}
+
+impl<T: Signed> Decode for ZigZag<T> {
+    const MIN_SIZE: usize = 1;
+
+    #[inline]
+    fn decode_be(buf: &[u8], offset: &mut usize) -> Result<Self, DecodeError> {
+        VarInt::<T::Unsigned>::decode_be(buf, offset).map(|v| ZigZag(T::unzigzag(v.0)))
+    }
+
+    #[inline]
+    fn decode_le(buf: &[u8], offset: &mut usize) -> Result<Self, DecodeError> {
+        Self::decode_be(buf, offset)
+    }
+}
+
+impl<T: Signed> EncodedSize for ZigZag<T> {
+    #[inline]
+    fn encoded_size(&self) -> usize {
+        VarInt(self.0.zigzag()).encoded_size()
+    }
+}
@@ -0,0 +1,83 @@
+//! 16 位字顺序颠倒的 32/64 位数值
+//!
+//! Modbus 等协议把 32 位数拆成两个大端寄存器, 低位寄存器在前 (CDAB)。
+//! [`WordSwapped`] 在给定字节序的基础上把各个 16 位字倒序排列,
+//! 字段上可以用 `#[serdeb(word_order = "swapped")]`。
+
+use crate::error::{DecodeError, EncodeError};
+use crate::{Decode, Encode, EncodedSize, Writer};
+
+/// 16 位字倒序编码的数值, 大端下 `0x1234_5678u32` 编码为 `56 78 12 34`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct WordSwapped<T>(pub T);
+
+macro_rules! impl_word_swapped {
+    ($($t:ty),+ $(,)?) => {
+        $(
+            impl Encode for WordSwapped<$t> {
+                #[inline]
+                fn encode_be<W: Writer>(&self, buf: &mut W) -> Result<(), EncodeError> {
+                    buf.write_slice(&swap_words(self.0.to_be_bytes()))
+                }
+
+                #[inline]
+                fn encode_le<W: Writer>(&self, buf: &mut W) -> Result<(), EncodeError> {
+                    buf.write_slice(&swap_words(self.0.to_le_bytes()))
+                }
+            }
+
+            impl Decode for WordSwapped<$t> {
+                const MIN_SIZE: usize = size_of::<$t>();
+
+                #[inline]
+                fn decode_be(buf: &[u8], offset: &mut usize) -> Result<Self, DecodeError> {
+                    read(buf, offset).map(|bytes| Self(<$t>::from_be_bytes(swap_words(bytes))))
+                }
+
+                #[inline]
+                fn decode_le(buf: &[u8], offset: &mut usize) -> Result<Self, DecodeError> {
+                    read(buf, offset).map(|bytes| Self(<$t>::from_le_bytes(swap_words(bytes))))
+                }
+            }
+
+            impl EncodedSize for WordSwapped<$t> {
+                const FIXED_SIZE: Option<usize> = Some(size_of::<$t>());
+
+                #[inline]
+                fn encoded_size(&self) -> usize {
+                    size_of::<$t>()
+                }
+            }
+
+            impl From<WordSwapped<$t>> for $t {
+                #[inline]
+                fn from(v: WordSwapped<$t>) -> Self {
+                    v.0
+                }
+            }
+        )+
+    };
+}
+
+impl_word_swapped!(u32, i32, f32, u64, i64, f64);
+
+/// 2 字节一组倒序, 组内字节顺序不变
+fn swap_words<const N: usize>(bytes: [u8; N]) -> [u8; N] {
+    let mut swapped = [0u8; N];
+    for (dst, src) in swapped.chunks_exact_mut(2).zip(bytes.chunks_exact(2).rev()) {
+        dst.copy_from_slice(src);
+    }
+    swapped
+}
+
+fn read<const N: usize>(buf: &[u8], offset: &mut usize) -> Result<[u8; N], DecodeError> {
+    match buf.get(*offset..*offset + N) {
+        Some(src) => {
+            *offset += N;
+            Ok(src.try_into().unwrap())
+        }
+        None => Err(DecodeError::UnexpectedEof {
+            needed: Some(*offset + N - buf.len()),
+        }),
+    }
+}
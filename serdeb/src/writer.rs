@@ -0,0 +1,221 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use bytes::BufMut;
+#[cfg(feature = "alloc")]
+use bytes::BytesMut;
+
+use crate::error::EncodeError;
+
+/// 编码输出, 只需要能追加写入
+///
+/// 派生的计算字段 (长度, 校验和) 还需要回填已写入的字节, 见 [`Backpatch`]
+pub trait Writer {
+    /// 追加字节, 空间不足时返回 `EncodeError::BufferFull`
+    fn write_slice(&mut self, src: &[u8]) -> Result<(), EncodeError>;
+
+    /// 支持回填时返回自身
+    #[inline]
+    fn as_backpatch(&mut self) -> Option<&mut dyn Backpatch> {
+        None
+    }
+
+    /// 是否保存写入的数据, 只计数时返回 `false`, 回填和校验和会被跳过
+    #[inline]
+    fn retains_data(&self) -> bool {
+        true
+    }
+}
+
+/// 可以访问已写入字节的输出, 用于回填占位字段和计算校验和
+pub trait Backpatch {
+    fn written(&self) -> &[u8];
+
+    fn written_mut(&mut self) -> &mut [u8];
+}
+
+/// 已写入的字节数, 用于记录计算字段的范围
+///
+/// 只计数的输出返回 0, 不支持回填的输出返回 `EncodeError::BackpatchUnsupported`
+#[doc(hidden)]
+pub fn position<W: Writer + ?Sized>(buf: &mut W) -> Result<usize, EncodeError> {
+    if !buf.retains_data() {
+        return Ok(0);
+    }
+    buf.as_backpatch()
+        .map(|patch| patch.written().len())
+        .ok_or(EncodeError::BackpatchUnsupported)
+}
+
+#[cfg(feature = "alloc")]
+impl Writer for BytesMut {
+    #[inline]
+    fn write_slice(&mut self, src: &[u8]) -> Result<(), EncodeError> {
+        self.extend_from_slice(src);
+        Ok(())
+    }
+
+    #[inline]
+    fn as_backpatch(&mut self) -> Option<&mut dyn Backpatch> {
+        Some(self)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Backpatch for BytesMut {
+    #[inline]
+    fn written(&self) -> &[u8] {
+        self
+    }
+
+    #[inline]
+    fn written_mut(&mut self) -> &mut [u8] {
+        self
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Writer for Vec<u8> {
+    #[inline]
+    fn write_slice(&mut self, src: &[u8]) -> Result<(), EncodeError> {
+        self.extend_from_slice(src);
+        Ok(())
+    }
+
+    #[inline]
+    fn as_backpatch(&mut self) -> Option<&mut dyn Backpatch> {
+        Some(self)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Backpatch for Vec<u8> {
+    #[inline]
+    fn written(&self) -> &[u8] {
+        self
+    }
+
+    #[inline]
+    fn written_mut(&mut self) -> &mut [u8] {
+        self
+    }
+}
+
+/// 写入切片的开头并把切片推进到未写入的部分, 与 `std::io::Write for &mut [u8]` 相同
+///
+/// 已写入的部分不再可见, 不支持回填, 需要回填时用 [`SliceWriter`]
+impl Writer for &mut [u8] {
+    #[inline]
+    fn write_slice(&mut self, src: &[u8]) -> Result<(), EncodeError> {
+        if src.len() > self.len() {
+            return Err(EncodeError::BufferFull {
+                needed: src.len() - self.len(),
+            });
+        }
+
+        let (head, tail) = core::mem::take(self).split_at_mut(src.len());
+        head.copy_from_slice(src);
+        *self = tail;
+        Ok(())
+    }
+}
+
+/// `bytes::BufMut::writer()` 得到的适配器, 任意 `BufMut` 都可以作为输出, 不支持回填
+#[cfg(feature = "std")]
+impl<B: BufMut> Writer for bytes::buf::Writer<B> {
+    #[inline]
+    fn write_slice(&mut self, src: &[u8]) -> Result<(), EncodeError> {
+        let buf = self.get_mut();
+        if src.len() > buf.remaining_mut() {
+            return Err(EncodeError::BufferFull {
+                needed: src.len() - buf.remaining_mut(),
+            });
+        }
+
+        buf.put_slice(src);
+        Ok(())
+    }
+}
+
+/// 写入调用方提供的定长缓冲区, 例如栈上数组或 DMA 区域
+#[derive(Debug)]
+pub struct SliceWriter<'a> {
+    buf: &'a mut [u8],
+    pos: usize,
+}
+
+impl<'a> SliceWriter<'a> {
+    pub fn new(buf: &'a mut [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    /// 剩余可写字节数
+    pub fn remaining(&self) -> usize {
+        self.buf.len() - self.pos
+    }
+
+    /// 取出已写入的部分
+    pub fn into_written(self) -> &'a mut [u8] {
+        &mut self.buf[..self.pos]
+    }
+}
+
+impl Writer for SliceWriter<'_> {
+    #[inline]
+    fn write_slice(&mut self, src: &[u8]) -> Result<(), EncodeError> {
+        if src.len() > self.remaining() {
+            return Err(EncodeError::BufferFull {
+                needed: src.len() - self.remaining(),
+            });
+        }
+
+        self.buf[self.pos..self.pos + src.len()].copy_from_slice(src);
+        self.pos += src.len();
+        Ok(())
+    }
+
+    #[inline]
+    fn as_backpatch(&mut self) -> Option<&mut dyn Backpatch> {
+        Some(self)
+    }
+}
+
+impl Backpatch for SliceWriter<'_> {
+    #[inline]
+    fn written(&self) -> &[u8] {
+        &self.buf[..self.pos]
+    }
+
+    #[inline]
+    fn written_mut(&mut self) -> &mut [u8] {
+        &mut self.buf[..self.pos]
+    }
+}
+
+/// 只统计写入的字节数, 用于计算自定义编码函数的长度, 不保存数据
+#[doc(hidden)]
+#[derive(Debug, Default)]
+pub struct SizeCounter(usize);
+
+impl Writer for SizeCounter {
+    #[inline]
+    fn write_slice(&mut self, src: &[u8]) -> Result<(), EncodeError> {
+        self.0 += src.len();
+        Ok(())
+    }
+
+    #[inline]
+    fn retains_data(&self) -> bool {
+        false
+    }
+}
+
+/// 调用编码函数并返回写入的字节数, 编码函数出错时返回它的错误
+#[doc(hidden)]
+pub fn encoded_size_with(
+    encode: impl FnOnce(&mut SizeCounter) -> Result<(), EncodeError>,
+) -> Result<usize, EncodeError> {
+    let mut counter = SizeCounter::default();
+    encode(&mut counter)?;
+    Ok(counter.0)
+}
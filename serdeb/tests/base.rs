@@ -8,12 +8,176 @@ fn test() {
 
     let str = "123456";
 
-    str.encode_str(&mut buf, Some(&[b'\0']));
+    str.encode_str(&mut buf, Some(b"\0")).unwrap();
 
-    let res = String::decode_str(&buf, &mut 0, Some(&[b'\0'])).unwrap();
+    let res = String::decode_str(&buf, &mut 0, Some(b"\0")).unwrap();
 
     assert_eq!(str, res);
 
     buf.clear();
+}
+
+#[test]
+fn checksum_check_values() {
+    use serdeb::checksum::{Checksum, Crc16Modbus, Crc32, Sum8, Xor8};
+
+    let data = b"123456789";
+
+    assert_eq!(Crc16Modbus::checksum(data), 0x4B37);
+    assert_eq!(Crc32::checksum(data), 0xCBF4_3926);
+    assert_eq!(Xor8::checksum(data), 0x31);
+    assert_eq!(Sum8::checksum(data), 0xDD);
+}
+
+#[test]
+fn fixed_point_rounding_and_bounds() {
+    use serdeb::fixed::FixedPoint;
+
+    // 0.5 远离零, 最接近 0.5 的小数不会被进位
+    assert_eq!(i32::from_f64(2.5), Some(3));
+    assert_eq!(i32::from_f64(-2.5), Some(-3));
+    assert_eq!(i32::from_f64(0.499_999_999_999_999_94), Some(0));
+    assert_eq!(i32::from_f64(-0.499_999_999_999_999_94), Some(0));
+    assert_eq!(
+        u64::from_f64(4_503_599_627_370_497.0),
+        Some(4_503_599_627_370_497)
+    );
+
+    // 边界值本身可以表示, 越界一点都不行
+    assert_eq!(i64::from_f64(i64::MIN as f64), Some(i64::MIN));
+    assert_eq!(i64::from_f64(9_223_372_036_854_775_808.0), None);
+    assert_eq!(u8::from_f64(255.4), Some(255));
+    assert_eq!(u8::from_f64(255.5), None);
+    assert_eq!(u8::from_f64(-0.4), Some(0));
+    assert_eq!(u8::from_f64(-0.5), None);
+    assert_eq!(i8::from_f64(-128.4), Some(-128));
+    assert_eq!(i8::from_f64(-128.5), None);
+    assert_eq!(u32::from_f64(f64::NAN), None);
+    assert_eq!(i32::from_f64(f64::INFINITY), None);
+
+    assert_eq!(
+        u128::from_f64(1e30),
+        Some(1_000_000_000_000_000_019_884_624_838_656)
+    );
+    assert_eq!(u128::from_f64(u128::MAX as f64), None);
+    assert_eq!(i128::from_f64(i128::MIN as f64), Some(i128::MIN));
+    assert_eq!(i128::from_f64(-(i128::MIN as f64)), None);
+    assert_eq!((-5i128).to_f64(), -5.0);
+}
+
+#[test]
+fn placeholder_fill() {
+    use bytes::BytesMut;
+    use serdeb::error::EncodeError;
+    use serdeb::{Endian, ReserveField};
+
+    let mut buf = BytesMut::new();
+    let len = buf.reserve_field::<u16>().unwrap();
+    let crc = buf.reserve_field::<u32>().unwrap();
+    buf.extend_from_slice(b"payload");
+
+    assert_eq!(len.position(), 0);
+    len.fill(&mut buf, 7, Endian::Big).unwrap();
+    crc.fill(&mut buf, 0x01020304, Endian::Little).unwrap();
+
+    assert_eq!(&buf[..], b"\x00\x07\x04\x03\x02\x01payload");
+
+    // 预留在另一个缓冲区中的位置
+    let mut short = BytesMut::from(&b"ab"[..]);
+    let mut other = BytesMut::from(&b"abcd"[..]);
+    let tail = other.reserve_field::<u16>().unwrap();
+    assert!(matches!(
+        tail.fill(&mut short, 1, Endian::Big),
+        Err(EncodeError::PlaceholderOutOfBounds {
+            pos: 4,
+            size: 2,
+            written: 2
+        })
+    ));
+}
+
+#[test]
+fn array_decode_drops_partial_elements() {
+    use std::cell::Cell;
+
+    use serdeb::Decode;
+    use serdeb::error::DecodeError;
+
+    thread_local! {
+        static DROPS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    #[derive(Debug)]
+    struct Tracked(#[allow(dead_code)] u8);
+
+    impl Drop for Tracked {
+        fn drop(&mut self) {
+            DROPS.set(DROPS.get() + 1);
+        }
+    }
+
+    impl Decode for Tracked {
+        fn decode_be(buf: &[u8], offset: &mut usize) -> Result<Self, DecodeError> {
+            u8::decode_be(buf, offset).map(Tracked)
+        }
+
+        fn decode_le(buf: &[u8], offset: &mut usize) -> Result<Self, DecodeError> {
+            u8::decode_le(buf, offset).map(Tracked)
+        }
+    }
+
+    let res = <[Tracked; 4]>::decode_be(&[1, 2], &mut 0);
+    assert!(res.is_err());
+    assert_eq!(DROPS.get(), 2);
+
+    let array = <[Tracked; 2]>::decode_be(&[1, 2], &mut 0).unwrap();
+    assert_eq!(DROPS.get(), 2);
+    drop(array);
+    assert_eq!(DROPS.get(), 4);
+}
+
+#[test]
+fn varint_encoding() {
+    use bytes::BytesMut;
+    use serdeb::error::DecodeError;
+    use serdeb::{Decode, Encode, EncodedSize, VarInt, ZigZag};
+
+    let mut buf = BytesMut::new();
+    VarInt(300u32).encode_be(&mut buf).unwrap();
+    assert_eq!(&buf[..], &[0xAC, 0x02]);
+    assert_eq!(VarInt(300u32).encoded_size(), 2);
+    assert_eq!(VarInt(0u32).encoded_size(), 1);
+
+    for (value, expected) in [(0i32, 0u8), (-1, 1), (1, 2), (-64, 127)] {
+        let mut buf = BytesMut::new();
+        ZigZag(value).encode_le(&mut buf).unwrap();
+        assert_eq!(&buf[..], &[expected]);
+        assert_eq!(ZigZag::<i32>::decode_le(&buf, &mut 0), Ok(ZigZag(value)));
+    }
+
+    let mut buf = BytesMut::new();
+    VarInt(u64::MAX).encode_be(&mut buf).unwrap();
+    assert_eq!(buf.len(), 10);
+    assert_eq!(VarInt::<u64>::decode_be(&buf, &mut 0), Ok(VarInt(u64::MAX)));
+
+    let mut buf = BytesMut::new();
+    ZigZag(i64::MIN).encode_be(&mut buf).unwrap();
+    assert_eq!(ZigZag::<i64>::decode_be(&buf, &mut 0), Ok(ZigZag(i64::MIN)));
 
+    assert_eq!(
+        VarInt::<u32>::decode_be(&[0x80, 0x00], &mut 0),
+        Err(DecodeError::VarIntOverlong)
+    );
+    assert_eq!(
+        VarInt::<u32>::decode_be(&[0xFF, 0xFF, 0xFF, 0xFF, 0x1F], &mut 0),
+        Err(DecodeError::VarIntOverflow)
+    );
+    assert_eq!(
+        VarInt::<u8>::decode_be(&[0x80, 0x80, 0x01], &mut 0),
+        Err(DecodeError::VarIntOverflow)
+    );
+    assert_eq!(
+        VarInt::<u32>::decode_be(&[0x80], &mut 0),
+        Err(DecodeError::UnexpectedEof { needed: Some(1) })
+    );
 }
@@ -0,0 +1,135 @@
+use futures_util::{SinkExt, StreamExt};
+use serdeb::codec::{PrefixWidth, SerdebCodec};
+use serdeb::error::CodecError;
+use serdeb::{Decoder, Encoder, Endian};
+use tokio_util::codec::{Decoder as _, Encoder as _, Framed};
+
+#[derive(Debug, Encoder, Decoder, PartialEq, Clone)]
+pub struct Message {
+    id: u16,
+    len: u8,
+    #[serdeb(len_from = len)]
+    body: Vec<u8>,
+}
+
+fn message(id: u16, body: &[u8]) -> Message {
+    Message {
+        id,
+        len: body.len() as u8,
+        body: body.to_vec(),
+    }
+}
+
+async fn roundtrip(codec: SerdebCodec<Message>) {
+    let (client, server) = tokio::io::duplex(16);
+    let mut client = Framed::new(client, codec.clone());
+    let mut server = Framed::new(server, codec);
+
+    let messages = [message(1, b"hello"), message(2, b""), message(3, b"abc")];
+
+    let send = async {
+        for m in &messages {
+            client.send(m).await.unwrap();
+        }
+    };
+    let recv = async {
+        let mut received = Vec::new();
+        for _ in 0..messages.len() {
+            received.push(server.next().await.unwrap().unwrap());
+        }
+        received
+    };
+
+    let ((), received) = tokio::join!(send, recv);
+    assert_eq!(received, messages);
+}
+
+#[tokio::test]
+async fn length_prefixed_over_duplex() {
+    roundtrip(SerdebCodec::length_prefixed(PrefixWidth::U16)).await;
+    roundtrip(SerdebCodec::length_prefixed(PrefixWidth::U32).endian(Endian::Little)).await;
+}
+
+#[tokio::test]
+async fn delimited_over_duplex() {
+    roundtrip(SerdebCodec::delimited(*b"\r\n")).await;
+}
+
+#[test]
+fn fixed_size_frames() {
+    let mut codec = SerdebCodec::<Message>::fixed(5);
+    let mut buf = bytes::BytesMut::new();
+
+    codec.encode(message(7, b"xy"), &mut buf).unwrap();
+    assert_eq!(&buf[..], &[0, 7, 2, b'x', b'y']);
+
+    assert!(matches!(
+        codec.encode(message(8, b"z"), &mut buf),
+        Err(CodecError::FrameSizeMismatch {
+            size: 4,
+            expected: 5
+        })
+    ));
+    assert_eq!(buf.len(), 5);
+
+    let mut partial = buf.split_to(3);
+    assert!(codec.decode(&mut partial).unwrap().is_none());
+    partial.unsplit(buf);
+    assert_eq!(codec.decode(&mut partial).unwrap(), Some(message(7, b"xy")));
+}
+
+#[test]
+fn max_frame_size_is_enforced() {
+    let mut codec = SerdebCodec::<Message>::length_prefixed(PrefixWidth::U16).max_frame_size(4);
+    let mut buf = bytes::BytesMut::new();
+
+    assert!(matches!(
+        codec.encode(message(1, b"too long"), &mut buf),
+        Err(CodecError::FrameTooLarge { size: 11, max: 4 })
+    ));
+    assert!(buf.is_empty());
+
+    buf.extend_from_slice(&[0x01, 0x00]);
+    assert!(matches!(
+        codec.decode(&mut buf),
+        Err(CodecError::FrameTooLarge { size: 256, max: 4 })
+    ));
+
+    let mut codec = SerdebCodec::<Message>::delimited(*b"\n").max_frame_size(4);
+    let mut buf = bytes::BytesMut::from(&b"0123456"[..]);
+    assert!(matches!(
+        codec.decode(&mut buf),
+        Err(CodecError::FrameTooLarge { .. })
+    ));
+}
+
+#[test]
+fn delimited_frames_arrive_in_pieces() {
+    let mut codec = SerdebCodec::<Message>::delimited(*b"\r\n");
+    let mut buf = bytes::BytesMut::new();
+
+    // 分隔符被拆在两次到达的数据中
+    for piece in [&[0, 1, 2][..], b"ab\r", b"\n"] {
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        buf.extend_from_slice(piece);
+    }
+    assert_eq!(codec.decode(&mut buf).unwrap(), Some(message(1, b"ab")));
+    assert!(buf.is_empty());
+}
+
+#[test]
+fn trailing_bytes_in_frame_are_rejected() {
+    let mut codec = SerdebCodec::<Message>::fixed(6);
+    let mut buf = bytes::BytesMut::from(&[0, 7, 2, b'x', b'y', 0xFF][..]);
+
+    assert!(matches!(
+        codec.decode(&mut buf),
+        Err(CodecError::TrailingBytes { extra: 1 })
+    ));
+}
+
+#[test]
+#[should_panic(expected = "delimiter must not be empty")]
+fn empty_delimiter_is_rejected() {
+    let _ = SerdebCodec::<Message>::delimited(Vec::new());
+}
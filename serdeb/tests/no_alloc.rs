@@ -0,0 +1,60 @@
+//! 只依赖 core 的部分: 定长结构体、位域和枚举
+//!
+//! 默认特性会打开 std, 需要用 `cargo test -p serdeb --no-default-features --features derive --test no_alloc` 运行
+
+#![no_std]
+
+use serdeb::error::{DecodeError, EncodeError};
+use serdeb::{Decode, Decoder, Encode, EncodedSize, Encoder, SliceWriter};
+
+#[derive(Debug, Encoder, Decoder, PartialEq, Eq, Clone, Copy, Default)]
+#[repr(u8)]
+pub enum Mode {
+    #[default]
+    Idle = 0,
+    Run = 2,
+}
+
+#[derive(Debug, Encoder, Decoder, PartialEq)]
+pub struct Status {
+    #[serdeb(bit_width = 4)]
+    channel: u8,
+    #[serdeb(bit_width = 4)]
+    level: u8,
+    mode: Mode,
+    uptime: u32,
+    samples: [i16; 3],
+}
+
+#[test]
+fn fixed_size_roundtrip_without_alloc() {
+    let status = Status {
+        channel: 3,
+        level: 9,
+        mode: Mode::Run,
+        uptime: 0x0102_0304,
+        samples: [-1, 0, 1],
+    };
+
+    let mut storage = [0u8; <Status as EncodedSize>::FIXED_SIZE.unwrap()];
+    let mut writer = SliceWriter::new(&mut storage);
+    status.encode_le(&mut writer).expect("encode failed");
+    assert_eq!(writer.remaining(), 0);
+
+    let decoded = Status::decode_le(&storage, &mut 0).expect("decode failed");
+    assert_eq!(decoded, status);
+
+    let too_wide = Status {
+        level: 16,
+        ..status
+    };
+    assert!(matches!(
+        too_wide.encode_le(&mut SliceWriter::new(&mut storage)),
+        Err(EncodeError::BitWidthLimit { value: 16, .. })
+    ));
+
+    assert_eq!(
+        Status::decode_le(&storage[..4], &mut 0),
+        Err(DecodeError::UnexpectedEof { needed: Some(2) })
+    );
+}
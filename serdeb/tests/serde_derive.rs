@@ -1,5 +1,6 @@
-use serdeb::{Decode, DecodeStr, Decoder, Encode, EncodeStr, Encoder};
 use bytes::BytesMut;
+use serdeb::checksum::Checksum;
+use serdeb::{Decode, Decoder, Encode, EncodedSize, Encoder};
 
 #[derive(Debug, Encoder, Decoder, Default, Clone, Copy, PartialEq)]
 #[repr(u8)]
@@ -20,7 +21,7 @@ pub struct P {
     #[serdeb(len_from = c)]
     vec: Vec<u8>,
     #[serdeb(bit_width = 2)]
-    d: E
+    d: E,
 }
 
 #[test]
@@ -50,3 +51,1888 @@ fn encode_decode_roundtrip() {
     // assert
     assert_eq!(decoded, p);
 }
+
+#[derive(Debug, Encoder, Decoder, PartialEq)]
+pub struct Conditional {
+    version: u8,
+    msg_type: u8,
+    #[serdeb(if = "version >= 2")]
+    flags: Option<u16>,
+    #[serdeb(if = "msg_type == 0x10", default)]
+    extra: u32,
+    tail: u8,
+}
+
+#[test]
+fn conditional_fields_roundtrip() {
+    let v1 = Conditional {
+        version: 1,
+        msg_type: 0x10,
+        flags: None,
+        extra: 7,
+        tail: 9,
+    };
+
+    let mut buf = BytesMut::with_capacity(64);
+    v1.encode_be(&mut buf).expect("encode failed");
+    assert_eq!(&buf[..], &[1, 0x10, 0, 0, 0, 7, 9]);
+    assert_eq!(Conditional::decode_be(&buf, &mut 0).unwrap(), v1);
+
+    let v2 = Conditional {
+        version: 2,
+        msg_type: 0x20,
+        flags: Some(0x0102),
+        extra: 0,
+        tail: 9,
+    };
+
+    buf.clear();
+    v2.encode_be(&mut buf).expect("encode failed");
+    assert_eq!(&buf[..], &[2, 0x20, 1, 2, 9]);
+    assert_eq!(Conditional::decode_be(&buf, &mut 0).unwrap(), v2);
+}
+
+#[test]
+fn conditional_fields_mismatch() {
+    let missing = Conditional {
+        version: 2,
+        msg_type: 0x20,
+        flags: None,
+        extra: 0,
+        tail: 9,
+    };
+    assert!(matches!(
+        missing.to_bytes_be(),
+        Err(serdeb::error::EncodeError::ConditionMismatch { field: "flags" })
+    ));
+
+    let unexpected = Conditional {
+        version: 1,
+        flags: Some(0x0102),
+        ..missing
+    };
+    assert!(matches!(
+        unexpected.to_bytes_le(),
+        Err(serdeb::error::EncodeError::ConditionMismatch { field: "flags" })
+    ));
+}
+
+#[derive(Debug, Encoder, Decoder, PartialEq)]
+pub struct Versioned {
+    id: u16,
+    #[serdeb(since = 3)]
+    rssi: i8,
+    #[serdeb(until = 5, default)]
+    legacy: u8,
+    #[serdeb(since = 2, until = 4)]
+    window: u16,
+}
+
+#[test]
+fn versioned_fields_roundtrip() {
+    let v = Versioned {
+        id: 0x0102,
+        rssi: -3,
+        legacy: 4,
+        window: 0x0506,
+    };
+
+    let mut buf = BytesMut::with_capacity(64);
+    v.encode_be(&mut buf).expect("encode failed");
+    assert_eq!(&buf[..], &[1, 2, 0xFD, 4, 5, 6]);
+
+    buf.clear();
+    v.encode_versioned_be(&mut buf, 1).expect("encode failed");
+    assert_eq!(&buf[..], &[1, 2, 4]);
+    let decoded = Versioned::decode_versioned_be(&buf, &mut 0, 1).unwrap();
+    assert_eq!(
+        decoded,
+        Versioned {
+            id: 0x0102,
+            rssi: 0,
+            legacy: 4,
+            window: 0,
+        }
+    );
+
+    buf.clear();
+    v.encode_versioned_le(&mut buf, 6).expect("encode failed");
+    assert_eq!(&buf[..], &[2, 1, 0xFD]);
+    let decoded = Versioned::decode_versioned_le(&buf, &mut 0, 6).unwrap();
+    assert_eq!(
+        decoded,
+        Versioned {
+            id: 0x0102,
+            rssi: -3,
+            legacy: 0,
+            window: 0,
+        }
+    );
+}
+
+#[derive(Debug, Encoder, Decoder, PartialEq)]
+pub struct Reading {
+    channel: u8,
+    #[serdeb(since = 2, default)]
+    quality: u8,
+}
+
+#[derive(Debug, Encoder, Decoder, PartialEq)]
+pub struct Report {
+    count: u8,
+    #[serdeb(len_from = count)]
+    readings: Vec<Reading>,
+    pair: [Reading; 2],
+    tagged: (u8, Reading),
+}
+
+#[test]
+fn nested_versioned_roundtrip() {
+    let reading = |channel| Reading {
+        channel,
+        quality: 9,
+    };
+    let report = Report {
+        count: 2,
+        readings: vec![reading(1), reading(2)],
+        pair: [reading(3), reading(4)],
+        tagged: (7, reading(5)),
+    };
+
+    let mut buf = BytesMut::new();
+    report
+        .encode_versioned_be(&mut buf, 1)
+        .expect("encode failed");
+    assert_eq!(&buf[..], &[2, 1, 2, 3, 4, 7, 5]);
+    let decoded = Report::decode_versioned_be(&buf, &mut 0, 1).unwrap();
+    let old = |channel| Reading {
+        channel,
+        quality: 0,
+    };
+    assert_eq!(
+        decoded,
+        Report {
+            count: 2,
+            readings: vec![old(1), old(2)],
+            pair: [old(3), old(4)],
+            tagged: (7, old(5)),
+        }
+    );
+
+    buf.clear();
+    report
+        .encode_versioned_le(&mut buf, 2)
+        .expect("encode failed");
+    assert_eq!(buf.len(), 12);
+    assert_eq!(
+        Report::decode_versioned_le(&buf, &mut 0, 2).unwrap(),
+        report
+    );
+}
+
+pub struct Complement8;
+
+impl serdeb::checksum::Checksum for Complement8 {
+    fn checksum(data: &[u8]) -> u64 {
+        data.iter()
+            .fold(0u8, |acc, b| acc.wrapping_add(*b))
+            .wrapping_neg() as u64
+    }
+}
+
+#[derive(Debug, Encoder, Decoder, PartialEq)]
+pub struct Frame {
+    start: u8,
+    len: u8,
+    #[serdeb(len_from = len)]
+    payload: Vec<u8>,
+    #[serdeb(checksum = "crc16_modbus", over = "start..=payload")]
+    crc: u16,
+    #[serdeb(checksum = Complement8, over = "len..crc")]
+    sum: u8,
+}
+
+#[test]
+fn checksum_fields_roundtrip() {
+    let frame = Frame {
+        start: 0x01,
+        len: 2,
+        payload: vec![0x03, 0x04],
+        crc: 0,
+        sum: 0,
+    };
+
+    let mut buf = BytesMut::with_capacity(64);
+    frame.encode_le(&mut buf).expect("encode failed");
+
+    let crc = serdeb::checksum::Crc16Modbus::checksum(&[0x01, 2, 0x03, 0x04]) as u16;
+    assert_eq!(
+        &buf[..6],
+        &[0x01, 2, 0x03, 0x04, crc as u8, (crc >> 8) as u8]
+    );
+    assert_eq!(buf[6], 0u8.wrapping_sub(2 + 3 + 4));
+
+    let decoded = Frame::decode_le(&buf, &mut 0).unwrap();
+    assert_eq!(decoded.crc, crc);
+    assert_eq!(decoded.payload, frame.payload);
+
+    buf[2] ^= 0xFF;
+    assert!(matches!(
+        Frame::decode_le(&buf, &mut 0),
+        Err(serdeb::error::DecodeError::ChecksumMismatch { field: "crc", .. })
+    ));
+}
+
+/// 校验字段与生成代码中的局部变量同名
+#[derive(Debug, Encoder, Decoder, PartialEq)]
+pub struct ShadowedChecksum {
+    data: u8,
+    #[serdeb(checksum = "xor8", over = "data..computed")]
+    computed: u8,
+}
+
+#[test]
+fn checksum_field_named_like_generated_local() {
+    let mut buf = BytesMut::with_capacity(8);
+    ShadowedChecksum {
+        data: 0x5A,
+        computed: 0,
+    }
+    .encode_be(&mut buf)
+    .unwrap();
+    assert_eq!(&buf[..], &[0x5A, 0x5A]);
+
+    buf[1] = 0;
+    assert!(matches!(
+        ShadowedChecksum::decode_be(&buf, &mut 0),
+        Err(serdeb::error::DecodeError::ChecksumMismatch {
+            field: "computed",
+            computed: 0x5A,
+            ..
+        })
+    ));
+}
+
+#[derive(Debug, Encoder, Decoder, PartialEq)]
+pub struct Packet {
+    #[serdeb(len_of = "kind..=body")]
+    size: u16,
+    #[serdeb(checksum = "xor8", over = "kind..=body")]
+    check: u8,
+    kind: u8,
+    count: u8,
+    #[serdeb(len_from = count)]
+    body: Vec<u16>,
+}
+
+#[test]
+fn computed_fields_are_backpatched() {
+    let packet = Packet {
+        size: 0,
+        check: 0,
+        kind: 0x11,
+        count: 2,
+        body: vec![0x0102, 0x0304],
+    };
+
+    let mut buf = BytesMut::with_capacity(64);
+    packet.encode_be(&mut buf).expect("encode failed");
+    assert_eq!(
+        &buf[..],
+        &[0, 6, 0x11 ^ 2 ^ 1 ^ 2 ^ 3 ^ 4, 0x11, 2, 1, 2, 3, 4]
+    );
+
+    let decoded = Packet::decode_be(&buf, &mut 0).unwrap();
+    assert_eq!(decoded.size, 6);
+    assert_eq!(decoded.body, packet.body);
+}
+
+fn encode_packet<W: serdeb::Writer>(
+    packet: &Packet,
+    buf: &mut W,
+    endian: serdeb::Endian,
+) -> Result<(), serdeb::error::EncodeError> {
+    match endian {
+        serdeb::Endian::Big => packet.encode_be(buf),
+        serdeb::Endian::Little => packet.encode_le(buf),
+    }
+}
+
+#[derive(Debug, Encoder)]
+pub struct Carrier {
+    tag: u8,
+    #[serdeb(encode_with = encode_packet)]
+    packet: Packet,
+}
+
+#[test]
+fn computed_fields_in_custom_codec_size() {
+    let carrier = Carrier {
+        tag: 0xEE,
+        packet: Packet {
+            size: 0,
+            check: 0,
+            kind: 0x11,
+            count: 1,
+            body: vec![0x0102],
+        },
+    };
+
+    assert_eq!(carrier.encoded_size(), 8);
+    let buf = carrier.to_bytes_be().expect("encode failed");
+    assert_eq!(&buf[..], &[0xEE, 0, 4, 0x11 ^ 1 ^ 1 ^ 2, 0x11, 1, 1, 2]);
+
+    // 自定义编码函数的错误不会被长度计算吞掉
+    let rejecting = Rejecting { tag: 1, value: 2 };
+    assert!(matches!(
+        rejecting.try_encoded_size(),
+        Err(serdeb::error::EncodeError::InvalidValue { field: "value" })
+    ));
+    assert!(matches!(
+        rejecting.to_bytes_be(),
+        Err(serdeb::error::EncodeError::InvalidValue { field: "value" })
+    ));
+}
+
+fn encode_rejected<W: serdeb::Writer>(
+    _value: &u8,
+    _buf: &mut W,
+    _endian: serdeb::Endian,
+) -> Result<(), serdeb::error::EncodeError> {
+    Err(serdeb::error::EncodeError::InvalidValue { field: "value" })
+}
+
+#[derive(Debug, Encoder)]
+pub struct Rejecting {
+    tag: u8,
+    #[serdeb(encode_with = encode_rejected)]
+    value: u8,
+}
+
+#[derive(Debug, Encoder, Decoder, PartialEq)]
+pub struct Fixed {
+    #[serdeb(bit_width = 3)]
+    kind: u8,
+    #[serdeb(bit_width = 5)]
+    flags: u8,
+    e: E,
+    pair: (u16, i32),
+    raw: [u8; 4],
+}
+
+const _: () = assert!(matches!(<Fixed as EncodedSize>::FIXED_SIZE, Some(12)));
+
+#[test]
+fn encoded_size_matches_encoding() {
+    assert_eq!(E::FIXED_SIZE, Some(1));
+    assert_eq!(P::FIXED_SIZE, None);
+    assert_eq!(Conditional::FIXED_SIZE, None);
+
+    let fixed = Fixed {
+        kind: 1,
+        flags: 2,
+        e: E::A,
+        pair: (3, -4),
+        raw: [5; 4],
+    };
+    let buf = fixed.to_bytes_le().expect("encode failed");
+    assert_eq!(buf.len(), 12);
+    assert_eq!(fixed.encoded_size(), 12);
+
+    let p = P {
+        a: 3,
+        b: 1,
+        c: 2,
+        str_: "123456".to_string(),
+        vec: vec![123, 255],
+        d: E::A,
+    };
+    let buf = p.to_bytes_be().expect("encode failed");
+    assert_eq!(p.encoded_size(), buf.len());
+
+    let v2 = Conditional {
+        version: 2,
+        msg_type: 0x20,
+        flags: Some(0x0102),
+        extra: 0,
+        tail: 9,
+    };
+    assert_eq!(v2.encoded_size(), 5);
+}
+
+#[test]
+fn encode_into_caller_buffer() {
+    use serdeb::SliceWriter;
+    use serdeb::error::EncodeError;
+
+    let fixed = Fixed {
+        kind: 1,
+        flags: 2,
+        e: E::A,
+        pair: (3, -4),
+        raw: [5; 4],
+    };
+    let expected = fixed.to_bytes_be().expect("encode failed");
+
+    let mut storage = [0u8; 16];
+    let mut writer = SliceWriter::new(&mut storage);
+    fixed.encode_be(&mut writer).expect("encode failed");
+    assert_eq!(writer.into_written(), &expected[..]);
+
+    let mut vec = Vec::new();
+    fixed.encode_be(&mut vec).expect("encode failed");
+    assert_eq!(vec, &expected[..]);
+
+    let mut small = [0u8; 8];
+    let mut writer = SliceWriter::new(&mut small);
+    assert!(matches!(
+        fixed.encode_be(&mut writer),
+        Err(EncodeError::BufferFull { .. })
+    ));
+
+    // 直接写入切片, 切片推进到未写入的部分
+    let mut storage = [0u8; 16];
+    let mut rest = &mut storage[..];
+    fixed.encode_be(&mut rest).expect("encode failed");
+    assert_eq!(rest.len(), 16 - expected.len());
+    assert_eq!(&storage[..expected.len()], &expected[..]);
+
+    // 任意 `BufMut` 通过 `writer()` 适配
+    let mut storage = [0u8; 16];
+    let mut writer = bytes::BufMut::writer(&mut storage[..]);
+    fixed.encode_be(&mut writer).expect("encode failed");
+    assert_eq!(&storage[..expected.len()], &expected[..]);
+
+    // 计算字段需要回填, 不支持回填的输出报错
+    let packet = Packet {
+        size: 0,
+        check: 0,
+        kind: 1,
+        count: 0,
+        body: Vec::new(),
+    };
+    let mut storage = [0u8; 16];
+    assert!(matches!(
+        packet.encode_be(&mut &mut storage[..]),
+        Err(EncodeError::BackpatchUnsupported)
+    ));
+    let mut writer = SliceWriter::new(&mut storage);
+    packet.encode_be(&mut writer).expect("encode failed");
+    assert_eq!(writer.into_written(), &[0, 2, 1, 1, 0]);
+}
+
+#[derive(Debug, Encoder, Decoder, PartialEq)]
+pub struct Chunked {
+    id: u32,
+    len: u16,
+    #[serdeb(len_from = len)]
+    payload: bytes::Bytes,
+    tail: u8,
+}
+
+#[test]
+fn decode_from_buf_and_bytes() {
+    use bytes::{Buf, Bytes};
+
+    let frame = Chunked {
+        id: 0x0A0B_0C0D,
+        len: 3,
+        payload: Bytes::from_static(b"abc"),
+        tail: 7,
+    };
+    let mut encoded = frame.to_bytes_be().expect("encode failed");
+    encoded.extend_from_slice(&[0xEE]);
+    let encoded = encoded.freeze();
+
+    // 报文跨越两个块
+    let (head, rest) = encoded.split_at(5);
+    let mut chain = head.chain(rest);
+    let decoded = Chunked::decode_from_buf_be(&mut chain).expect("decode failed");
+    assert_eq!(decoded, frame);
+    assert_eq!(chain.remaining(), 1);
+
+    // 数据不足时不推进
+    let mut short = &encoded[..6];
+    assert_eq!(
+        Chunked::decode_from_buf_be(&mut short),
+        Err(serdeb::error::DecodeError::UnexpectedEof { needed: Some(3) })
+    );
+    assert_eq!(short.len(), 6);
+
+    let mut source = encoded.clone();
+    let decoded = Chunked::decode_from_bytes_be(&mut source).expect("decode failed");
+    assert_eq!(decoded, frame);
+    assert_eq!(source.as_ref(), &[0xEE]);
+    assert!(encoded.as_ptr_range().contains(&decoded.payload.as_ptr()));
+
+    // 嵌套结构体中的 `Bytes` 字段同样零拷贝
+    let outer = Outer { kind: 2, frame };
+    let encoded = outer.to_bytes_be().expect("encode failed").freeze();
+    let mut source = encoded.clone();
+    let decoded = Outer::decode_from_bytes_be(&mut source).expect("decode failed");
+    assert_eq!(decoded, outer);
+    assert!(source.is_empty());
+    assert!(
+        encoded
+            .as_ptr_range()
+            .contains(&decoded.frame.payload.as_ptr())
+    );
+}
+
+#[derive(Debug, Encoder, Decoder, PartialEq)]
+pub struct Outer {
+    kind: u8,
+    frame: Chunked,
+}
+
+#[test]
+fn decode_from_buf_with_many_chunks() {
+    use bytes::Buf;
+    use std::io::IoSlice;
+
+    /// 每个块只有一个字节
+    struct Bytewise<'a>(&'a [u8]);
+
+    impl Buf for Bytewise<'_> {
+        fn remaining(&self) -> usize {
+            self.0.len()
+        }
+
+        fn chunk(&self) -> &[u8] {
+            &self.0[..self.0.len().min(1)]
+        }
+
+        fn advance(&mut self, cnt: usize) {
+            self.0 = &self.0[cnt..];
+        }
+
+        fn chunks_vectored<'b>(&'b self, dst: &mut [IoSlice<'b>]) -> usize {
+            let count = dst.len().min(self.0.len());
+            for (dst, byte) in dst.iter_mut().zip(self.0.chunks(1)) {
+                *dst = IoSlice::new(byte);
+            }
+            count
+        }
+    }
+
+    let frame = Chunked {
+        id: 1,
+        len: 200,
+        payload: bytes::Bytes::from(vec![0x5A; 200]),
+        tail: 9,
+    };
+    let encoded = frame.to_bytes_le().expect("encode failed");
+
+    let mut buf = Bytewise(&encoded);
+    assert_eq!(Chunked::decode_from_buf_le(&mut buf), Ok(frame));
+    assert_eq!(buf.remaining(), 0);
+
+    let mut short = Bytewise(&encoded[..100]);
+    assert_eq!(
+        Chunked::decode_from_buf_le(&mut short),
+        Err(serdeb::error::DecodeError::UnexpectedEof { needed: Some(106) })
+    );
+    assert_eq!(short.remaining(), 100);
+
+    // 逐字节的块拼成的大报文也只需要少数几次重新解码
+    let blob = Blob {
+        len: 1 << 16,
+        data: (0..1u32 << 16).map(|i| i as u8).collect(),
+    };
+    let encoded = blob.to_bytes_be().expect("encode failed");
+    let mut buf = Bytewise(&encoded);
+    assert_eq!(Blob::decode_from_buf_be(&mut buf), Ok(blob));
+    assert_eq!(buf.remaining(), 0);
+}
+
+#[test]
+fn stream_encode_decode() {
+    use serdeb::error::{DecodeError, ReadError};
+    use std::io::{Cursor, Read};
+
+    let p = P {
+        a: 3,
+        b: 1,
+        c: 2,
+        str_: "123456".to_string(),
+        vec: vec![123, 255],
+        d: E::A,
+    };
+    let frame = Chunked {
+        id: 1,
+        len: 2,
+        payload: bytes::Bytes::from_static(b"hi"),
+        tail: 3,
+    };
+
+    let mut file = Vec::new();
+    p.encode_to_writer_be(&mut file).expect("write failed");
+    frame.encode_to_writer_le(&mut file).expect("write failed");
+    file.push(0xAA);
+
+    let mut reader = Cursor::new(&file);
+    assert_eq!(P::decode_from_reader_be(&mut reader).unwrap(), p);
+    assert_eq!(Chunked::decode_from_reader_le(&mut reader).unwrap(), frame);
+
+    // 不会多读后续内容
+    let mut rest = Vec::new();
+    reader.read_to_end(&mut rest).unwrap();
+    assert_eq!(rest, [0xAA]);
+
+    let mut truncated = &file[..file.len() - 3];
+    P::decode_from_reader_be(&mut truncated).unwrap();
+    assert!(matches!(
+        Chunked::decode_from_reader_le(&mut truncated),
+        Err(ReadError::Decode(DecodeError::UnexpectedEof { .. }))
+    ));
+}
+
+#[derive(Debug, Encoder, Decoder, PartialEq)]
+pub struct Trailer {
+    id: u8,
+    text: String,
+}
+
+#[test]
+fn stream_decode_reads_undelimited_string_to_end() {
+    let trailer = Trailer {
+        id: 7,
+        text: "hi".to_string(),
+    };
+    let encoded = trailer.to_bytes_be().expect("encode failed");
+    assert_eq!(Trailer::decode_be(&encoded, &mut 0).unwrap(), trailer);
+
+    let mut reader = &encoded[..];
+    assert_eq!(
+        Trailer::decode_from_reader_be(&mut reader).unwrap(),
+        trailer
+    );
+    assert!(reader.is_empty());
+}
+
+#[derive(Debug, Encoder, Decoder, PartialEq)]
+pub struct Blob {
+    len: u32,
+    #[serdeb(len_from = len)]
+    data: Vec<u8>,
+}
+
+#[test]
+fn stream_decode_large_payload() {
+    use std::io::{BufReader, Read};
+
+    let blob = Blob {
+        len: 1 << 20,
+        data: (0..1u32 << 20).map(|i| i as u8).collect(),
+    };
+    let mut encoded = blob.to_bytes_be().expect("encode failed").to_vec();
+    encoded.push(0xAA);
+
+    // 缓冲区很小, 每次只有 64 字节可读
+    let mut reader = BufReader::with_capacity(64, &encoded[..]);
+    assert_eq!(Blob::decode_from_reader_be(&mut reader).unwrap(), blob);
+
+    let mut rest = Vec::new();
+    reader.read_to_end(&mut rest).unwrap();
+    assert_eq!(rest, [0xAA]);
+
+    assert_eq!(
+        Blob::decode_partial_be(&encoded[..100], &mut 0),
+        Err(serdeb::error::DecodeError::Incomplete {
+            needed: Some((1 << 20) - 96)
+        })
+    );
+}
+
+#[test]
+fn decode_partial_reports_needed_bytes() {
+    use serdeb::error::DecodeError;
+
+    let frame = Chunked {
+        id: 9,
+        len: 4,
+        payload: bytes::Bytes::from_static(b"data"),
+        tail: 1,
+    };
+    let encoded = frame.to_bytes_be().expect("encode failed");
+
+    let mut received = Vec::new();
+    let mut offset = 0;
+    let mut chunks = encoded.chunks(3);
+
+    let decoded = loop {
+        match Chunked::decode_partial_be(&received, &mut offset) {
+            Ok(v) => break v,
+            Err(DecodeError::Incomplete { needed }) => {
+                assert_eq!(offset, 0);
+                assert!(needed.is_some_and(|n| n > 0));
+                received.extend_from_slice(chunks.next().expect("ran out of input"));
+            }
+            Err(e) => panic!("unexpected error: {e}"),
+        }
+    };
+
+    assert_eq!(decoded, frame);
+    assert_eq!(offset, encoded.len());
+
+    let mut offset = 0;
+    assert_eq!(
+        Chunked::decode_partial_be(&encoded[..7], &mut offset),
+        Err(DecodeError::Incomplete { needed: Some(3) })
+    );
+    assert_eq!(offset, 0);
+}
+
+#[derive(Debug, Encoder, Decoder, Clone, PartialEq)]
+pub struct WithDefault {
+    a: u16,
+    #[serdeb(default)]
+    kind: E,
+    #[serdeb(default)]
+    b: u32,
+}
+
+#[test]
+fn default_fields_only_fill_absent_input() {
+    use serdeb::error::DecodeError;
+    use std::io::{BufReader, Read};
+
+    let absent = WithDefault {
+        a: 1,
+        kind: E::A,
+        b: 0,
+    };
+    assert_eq!(WithDefault::decode_be(&[0, 1], &mut 0), Ok(absent.clone()));
+
+    // 字段只到了一部分, 或者内容有误时不取默认值
+    assert_eq!(
+        WithDefault::decode_be(&[0, 1, 1, 0, 0], &mut 0),
+        Err(DecodeError::UnexpectedEof { needed: Some(2) })
+    );
+    assert_eq!(
+        WithDefault::decode_be(&[0, 1, 7], &mut 0),
+        Err(DecodeError::InvalidDiscriminant {
+            name: "E",
+            value: 7
+        })
+    );
+
+    // 数据可能还没到齐时等待后续数据
+    let mut offset = 0;
+    assert_eq!(
+        WithDefault::decode_partial_be(&[0, 1], &mut offset),
+        Err(DecodeError::Incomplete { needed: Some(1) })
+    );
+    assert_eq!(offset, 0);
+    assert_eq!(
+        WithDefault::decode_partial_be(&[0, 1, 1, 0], &mut 0),
+        Err(DecodeError::Incomplete { needed: Some(3) })
+    );
+
+    let full = [0, 1, 1, 0, 0, 0, 2, 0xAA];
+    let mut reader = BufReader::with_capacity(2, &full[..]);
+    assert_eq!(
+        WithDefault::decode_from_reader_be(&mut reader).unwrap(),
+        WithDefault {
+            a: 1,
+            kind: E::A,
+            b: 2
+        }
+    );
+    let mut rest = Vec::new();
+    reader.read_to_end(&mut rest).unwrap();
+    assert_eq!(rest, [0xAA]);
+
+    // 流结束时末尾缺失的字段取默认值
+    let mut reader = BufReader::with_capacity(1, &[0u8, 1][..]);
+    assert_eq!(
+        WithDefault::decode_from_reader_be(&mut reader).unwrap(),
+        absent
+    );
+}
+
+#[derive(Debug, Encoder, Decoder, PartialEq)]
+pub struct SyncFrame {
+    magic: u16,
+    len: u8,
+    #[serdeb(len_from = len)]
+    payload: Vec<u8>,
+    #[serdeb(checksum = "crc16_modbus", over = "magic..=payload")]
+    crc: u16,
+}
+
+#[test]
+fn frame_scanner_resyncs_on_noise() {
+    use serdeb::sync::{FrameScanner, ScanStats};
+
+    let frame = |payload: &[u8]| SyncFrame {
+        magic: 0xA55A,
+        len: payload.len() as u8,
+        payload: payload.to_vec(),
+        crc: 0,
+    };
+    let first = frame(b"one").to_bytes_be().unwrap();
+    let second = frame(b"two!").to_bytes_be().unwrap();
+    let mut corrupt = frame(b"bad").to_bytes_be().unwrap();
+    corrupt[4] ^= 0xFF;
+
+    let mut stream = vec![0x00, 0xA5, 0x13];
+    stream.extend_from_slice(&first);
+    stream.extend_from_slice(&corrupt);
+    stream.extend_from_slice(&[0x77, 0xA5]);
+    stream.extend_from_slice(&second);
+
+    let mut scanner = FrameScanner::<SyncFrame>::new([0xA5, 0x5A]);
+    let mut frames = Vec::new();
+    // 分多次到达
+    for chunk in stream.chunks(5) {
+        scanner.push(chunk);
+        while let Some(f) = scanner.next_frame() {
+            frames.push(f);
+        }
+    }
+
+    let payloads: Vec<_> = frames.iter().map(|f| f.payload.clone()).collect();
+    assert_eq!(payloads, [b"one".to_vec(), b"two!".to_vec()]);
+    assert_eq!(scanner.buffered(), 0);
+    assert_eq!(
+        scanner.stats(),
+        ScanStats {
+            frames: 2,
+            discarded_bytes: (3 + corrupt.len() + 2) as u64,
+            bad_frames: 1,
+        }
+    );
+}
+
+#[test]
+fn frame_scanner_skips_false_sync_with_corrupt_length() {
+    use serdeb::sync::{FrameScanner, ScanStats};
+
+    let frame = SyncFrame {
+        magic: 0xA55A,
+        len: 3,
+        payload: b"one".to_vec(),
+        crc: 0,
+    };
+    // 误匹配的同步字后面是一个很大的长度
+    let mut stream = vec![0xA5, 0x5A, 0xFF];
+    stream.extend_from_slice(&frame.to_bytes_be().unwrap());
+
+    // 长度超出上限, 立即跳过
+    let mut scanner = FrameScanner::<SyncFrame>::new([0xA5, 0x5A]).max_frame_size(64);
+    scanner.push(&stream);
+    assert_eq!(scanner.next_frame().unwrap().payload, b"one");
+    assert_eq!(scanner.next_frame(), None);
+    assert_eq!(scanner.buffered(), 0);
+    assert_eq!(
+        scanner.stats(),
+        ScanStats {
+            frames: 1,
+            discarded_bytes: 3,
+            bad_frames: 1,
+        }
+    );
+
+    // 长度没有超出上限时等到它收完, 校验失败后再从下一个同步字开始
+    let mut scanner = FrameScanner::<SyncFrame>::new([0xA5, 0x5A]);
+    scanner.push(&stream);
+    assert_eq!(scanner.next_frame(), None);
+    assert_eq!(scanner.buffered(), stream.len());
+
+    scanner.push(&[0; 256]);
+    assert_eq!(scanner.next_frame().unwrap().payload, b"one");
+    assert_eq!(scanner.next_frame(), None);
+    assert_eq!(scanner.stats().bad_frames, 1);
+}
+
+#[test]
+fn frame_scanner_waits_for_payload_containing_sync() {
+    use serdeb::sync::{FrameScanner, ScanStats};
+
+    let frame = SyncFrame {
+        magic: 0xA55A,
+        len: 5,
+        payload: vec![0xA5, 0x5A, 0x01, 0xA5, 0x5A],
+        crc: 0,
+    };
+    let encoded = frame.to_bytes_be().unwrap();
+
+    let mut scanner = FrameScanner::<SyncFrame>::new([0xA5, 0x5A]);
+    let mut frames = Vec::new();
+    for chunk in encoded.chunks(2) {
+        scanner.push(chunk);
+        frames.extend(scanner.next_frame());
+    }
+
+    assert_eq!(frames.len(), 1);
+    assert_eq!(frames[0].payload, [0xA5, 0x5A, 0x01, 0xA5, 0x5A]);
+    assert_eq!(scanner.buffered(), 0);
+    assert_eq!(
+        scanner.stats(),
+        ScanStats {
+            frames: 1,
+            discarded_bytes: 0,
+            bad_frames: 0,
+        }
+    );
+}
+
+#[derive(Debug, Encoder, Decoder, PartialEq)]
+pub struct Named {
+    id: u8,
+    #[serdeb(delimiter = b'\0')]
+    name: String,
+}
+
+#[derive(Debug, Encoder, Decoder, PartialEq)]
+pub struct Arrays {
+    #[serdeb(delimiter = b'\0')]
+    labels: [String; 3],
+    len: u8,
+    #[serdeb(len_from = len)]
+    blobs: [Vec<u8>; 2],
+    matrix: [[u16; 4]; 4],
+    named: [Named; 2],
+}
+
+#[test]
+fn arrays_of_non_copy_elements() {
+    let arrays = Arrays {
+        labels: ["a".to_string(), "bc".to_string(), String::new()],
+        len: 2,
+        blobs: [vec![1, 2], vec![3, 4]],
+        matrix: [
+            [1, 2, 3, 4],
+            [5, 6, 7, 8],
+            [9, 10, 11, 12],
+            [13, 14, 15, 16],
+        ],
+        named: [
+            Named {
+                id: 1,
+                name: "x".to_string(),
+            },
+            Named {
+                id: 2,
+                name: "yz".to_string(),
+            },
+        ],
+    };
+
+    let buf = arrays.to_bytes_le().expect("encode failed");
+    assert_eq!(buf.len(), arrays.encoded_size());
+    assert_eq!(&buf[..7], b"a\0bc\0\0\x02");
+
+    let decoded = Arrays::decode_le(&buf, &mut 0).expect("decode failed");
+    assert_eq!(decoded, arrays);
+
+    assert!(matches!(
+        Arrays::decode_le(&buf[..buf.len() - 1], &mut 0),
+        Err(serdeb::error::DecodeError::UnexpectedEof { .. })
+    ));
+}
+
+#[derive(Debug, Encoder, Decoder, PartialEq)]
+pub struct Collections {
+    boxed: Box<Named>,
+    shared: std::rc::Rc<u32>,
+    #[serdeb(delimiter = b'\0')]
+    label: std::sync::Arc<str>,
+    #[serdeb(delimiter = b'\0')]
+    note: std::borrow::Cow<'static, str>,
+    #[serdeb(len_prefix = u8)]
+    raw: std::borrow::Cow<'static, [u8]>,
+    count: u8,
+    #[serdeb(len_from = count)]
+    queue: std::collections::VecDeque<u16>,
+    #[serdeb(len_from = count)]
+    sliced: Box<[u8]>,
+    #[serdeb(len_prefix = u16)]
+    table: std::collections::BTreeMap<u8, u16>,
+    #[serdeb(len_prefix = u8)]
+    lookup: std::collections::HashMap<u16, u8>,
+    #[serdeb(len_prefix = u8)]
+    set: std::collections::BTreeSet<u32>,
+    #[serdeb(len_prefix = u8, delimiter = b';')]
+    words: Vec<String>,
+}
+
+#[test]
+fn collections_and_pointers_roundtrip() {
+    let collections = Collections {
+        boxed: Box::new(Named {
+            id: 1,
+            name: "n".to_string(),
+        }),
+        shared: std::rc::Rc::new(7),
+        label: "label".into(),
+        note: "note".into(),
+        raw: vec![9, 8].into(),
+        count: 2,
+        queue: [1, 2].into(),
+        sliced: vec![3, 4].into_boxed_slice(),
+        table: [(1, 10), (2, 20)].into(),
+        lookup: [(5, 50)].into(),
+        set: [3, 1, 2].into(),
+        words: vec!["ab".to_string(), "c".to_string()],
+    };
+
+    let buf = collections.to_bytes_be().expect("encode failed");
+    assert_eq!(buf.len(), collections.encoded_size());
+    // raw: 长度前缀后跟内容
+    assert_eq!(&buf[18..21], &[2, 9, 8]);
+    assert_eq!(&buf[buf.len() - 6..], b"\x02ab;c;");
+
+    let decoded = Collections::decode_be(&buf, &mut 0).expect("decode failed");
+    assert_eq!(decoded, collections);
+
+    let mut too_long = Collections::decode_be(&buf, &mut 0).unwrap();
+    too_long.raw = vec![0; 256].into();
+    assert!(matches!(
+        too_long.to_bytes_be(),
+        Err(serdeb::error::EncodeError::LengthOverflow {
+            field: "raw",
+            len: 256
+        })
+    ));
+}
+
+#[derive(Debug, Encoder, Decoder, PartialEq)]
+pub struct VarInts {
+    #[serdeb(varint)]
+    id: u64,
+    #[serdeb(varint, zigzag)]
+    delta: i32,
+    #[serdeb(varint, zigzag, len_prefix = serdeb::VarInt<u32>)]
+    deltas: Vec<i16>,
+    #[serdeb(len_prefix = serdeb::VarInt<u16>)]
+    raw: Vec<u8>,
+    tail: u8,
+}
+
+#[derive(Debug, Encoder, Decoder, PartialEq)]
+pub struct Row {
+    len: u8,
+    #[serdeb(len_from = len)]
+    cells: Vec<u8>,
+}
+
+#[derive(Debug, Encoder, Decoder, PartialEq)]
+pub struct Matrix {
+    rows: u8,
+    #[serdeb(len_from = rows)]
+    data: Vec<Row>,
+}
+
+#[test]
+fn nested_collections_have_their_own_length() {
+    let matrix = Matrix {
+        rows: 3,
+        data: vec![
+            Row {
+                len: 1,
+                cells: vec![1],
+            },
+            Row {
+                len: 0,
+                cells: vec![],
+            },
+            Row {
+                len: 2,
+                cells: vec![2, 3],
+            },
+        ],
+    };
+
+    let buf = matrix.to_bytes_be().expect("encode failed");
+    assert_eq!(&buf[..], &[3, 1, 1, 0, 2, 2, 3]);
+    assert_eq!(Matrix::decode_be(&buf, &mut 0), Ok(matrix));
+}
+
+#[test]
+fn varint_fields_roundtrip() {
+    let value = VarInts {
+        id: 300,
+        delta: -2,
+        deltas: vec![1, -1, 100],
+        raw: vec![0xAB; 200],
+        tail: 0xFF,
+    };
+
+    let buf = value.to_bytes_be().expect("encode failed");
+    assert_eq!(buf.len(), value.encoded_size());
+    assert_eq!(&buf[..8], &[0xAC, 0x02, 0x03, 0x03, 0x02, 0x01, 0xC8, 0x01]);
+    assert_eq!(&buf[8..10], &[0xC8, 0x01]);
+    assert_eq!(buf.len(), 2 + 1 + 1 + 4 + 2 + 200 + 1);
+
+    let decoded = VarInts::decode_be(&buf, &mut 0).expect("decode failed");
+    assert_eq!(decoded, value);
+}
+
+#[derive(Debug, Encoder, Decoder, PartialEq)]
+pub struct Sensor {
+    #[serdeb(as = u16, scale = 0.01, offset = -40.0)]
+    temperature: f32,
+    #[serdeb(as = i16, scale = 0.1)]
+    pressure: f64,
+    #[serdeb(bit_width = 4)]
+    channel: u8,
+    #[serdeb(bit_width = 12, as = u16, scale = 0.5)]
+    humidity: f32,
+}
+
+#[test]
+fn fixed_point_fields() {
+    let value = Sensor {
+        temperature: 25.0,
+        pressure: -12.3,
+        channel: 3,
+        humidity: 45.5,
+    };
+
+    let buf = value.to_bytes_be().expect("encode failed");
+    assert_eq!(buf.len(), value.encoded_size());
+    assert_eq!(Sensor::FIXED_SIZE, Some(6));
+    // (25.0 + 40.0) / 0.01 = 6500, -12.3 / 0.1 = -123
+    assert_eq!(&buf[..4], &[0x19, 0x64, 0xFF, 0x85]);
+
+    let decoded = Sensor::decode_be(&buf, &mut 0).expect("decode failed");
+    assert!((decoded.temperature - 25.0).abs() < 1e-4);
+    assert!((decoded.pressure + 12.3).abs() < 1e-9);
+    assert_eq!(decoded.channel, 3);
+    assert_eq!(decoded.humidity, 45.5);
+
+    let buf = value.to_bytes_le().expect("encode failed");
+    let decoded = Sensor::decode_le(&buf, &mut 0).expect("decode failed");
+    assert_eq!(decoded.humidity, 45.5);
+
+    let out_of_range = Sensor {
+        temperature: 700.0,
+        ..value
+    };
+    assert!(matches!(
+        out_of_range.to_bytes_be(),
+        Err(serdeb::error::EncodeError::FixedPointOutOfRange {
+            field: "temperature",
+            ..
+        })
+    ));
+}
+
+mod bcd {
+    use serdeb::error::{DecodeError, EncodeError};
+    use serdeb::{Endian, Writer};
+
+    /// HHMMSS 编码为 3 字节 BCD
+    pub fn encode<W: Writer>(value: &u32, buf: &mut W, _endian: Endian) -> Result<(), EncodeError> {
+        let mut bytes = [0u8; 3];
+        let mut v = *value;
+        for byte in bytes.iter_mut().rev() {
+            *byte = (((v / 10 % 10) << 4) | (v % 10)) as u8;
+            v /= 100;
+        }
+        buf.write_slice(&bytes)
+    }
+
+    pub fn decode(buf: &[u8], offset: &mut usize, _endian: Endian) -> Result<u32, DecodeError> {
+        let bytes = buf
+            .get(*offset..*offset + 3)
+            .ok_or_else(|| DecodeError::UnexpectedEof {
+                needed: Some(*offset + 3 - buf.len()),
+            })?;
+        *offset += 3;
+        Ok(bytes
+            .iter()
+            .fold(0, |v, b| v * 100 + (b >> 4) as u32 * 10 + (b & 0x0F) as u32))
+    }
+}
+
+mod tagged {
+    use serdeb::error::{DecodeError, EncodeError};
+    use serdeb::{Endian, Writer};
+
+    pub fn encode<W: Writer>(
+        value: &Vec<u16>,
+        buf: &mut W,
+        endian: Endian,
+        _kind: &u8,
+    ) -> Result<(), EncodeError> {
+        for v in value {
+            match endian {
+                Endian::Big => buf.write_slice(&v.to_be_bytes())?,
+                Endian::Little => buf.write_slice(&v.to_le_bytes())?,
+            }
+        }
+        Ok(())
+    }
+
+    /// 元素个数由已解码的 kind 决定
+    pub fn decode(
+        buf: &[u8],
+        offset: &mut usize,
+        endian: Endian,
+        kind: &u8,
+    ) -> Result<Vec<u16>, DecodeError> {
+        let len = if *kind == 1 { 1 } else { 2 };
+        (0..len)
+            .map(|_| match endian {
+                Endian::Big => serdeb::Decode::decode_be(buf, offset),
+                Endian::Little => serdeb::Decode::decode_le(buf, offset),
+            })
+            .collect()
+    }
+}
+
+fn lenient_bool(
+    buf: &[u8],
+    offset: &mut usize,
+    _endian: serdeb::Endian,
+) -> Result<bool, serdeb::error::DecodeError> {
+    u8::decode_be(buf, offset).map(|v| v != 0)
+}
+
+#[derive(Debug, Encoder, Decoder, PartialEq)]
+pub struct Custom {
+    #[serdeb(with = bcd)]
+    time: u32,
+    kind: u8,
+    #[serdeb(with = tagged, ctx = kind)]
+    values: Vec<u16>,
+    #[serdeb(decode_with = lenient_bool)]
+    flag: bool,
+}
+
+#[test]
+fn custom_codec_functions() {
+    let value = Custom {
+        time: 235_959,
+        kind: 2,
+        values: vec![0x0102, 0x0304],
+        flag: true,
+    };
+
+    let buf = value.to_bytes_be().expect("encode failed");
+    assert_eq!(buf.len(), value.encoded_size());
+    assert_eq!(
+        &buf[..],
+        &[0x23, 0x59, 0x59, 0x02, 0x01, 0x02, 0x03, 0x04, 0x01]
+    );
+    assert_eq!(
+        Custom::decode_be(&buf, &mut 0).expect("decode failed"),
+        value
+    );
+
+    let buf = value.to_bytes_le().expect("encode failed");
+    assert_eq!(&buf[4..8], &[0x02, 0x01, 0x04, 0x03]);
+    assert_eq!(
+        Custom::decode_le(&buf, &mut 0).expect("decode failed"),
+        value
+    );
+
+    let buf = [0x12, 0x00, 0x00, 0x01, 0xAB, 0xCD, 0x05];
+    let decoded = Custom::decode_be(&buf, &mut 0).expect("decode failed");
+    assert_eq!(decoded.time, 120_000);
+    assert_eq!(decoded.values, vec![0xABCD]);
+    assert!(decoded.flag);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    Off,
+    On,
+    Auto,
+}
+
+impl From<Mode> for u8 {
+    fn from(mode: Mode) -> u8 {
+        mode as u8
+    }
+}
+
+impl TryFrom<u8> for Mode {
+    type Error = ();
+
+    fn try_from(v: u8) -> Result<Self, ()> {
+        match v {
+            0 => Ok(Mode::Off),
+            1 => Ok(Mode::On),
+            2 => Ok(Mode::Auto),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Millis(u32);
+
+impl From<Millis> for u16 {
+    fn from(v: Millis) -> u16 {
+        v.0 as u16
+    }
+}
+
+impl From<u16> for Millis {
+    fn from(v: u16) -> Self {
+        Millis(v as u32)
+    }
+}
+
+#[derive(Debug, Encoder, Decoder, PartialEq)]
+pub struct Converted {
+    #[serdeb(as = u16)]
+    timeout: Millis,
+    #[serdeb(bit_width = 2, as = u8)]
+    mode: Mode,
+    #[serdeb(bit_width = 6)]
+    reserved: u8,
+    count: u8,
+    #[serdeb(as = u8, len_from = count)]
+    modes: Vec<Mode>,
+    #[serdeb(as = u8)]
+    pair: [Mode; 2],
+}
+
+#[test]
+fn wire_type_conversion() {
+    let value = Converted {
+        timeout: Millis(500),
+        mode: Mode::Auto,
+        reserved: 0,
+        count: 2,
+        modes: vec![Mode::On, Mode::Off],
+        pair: [Mode::Auto, Mode::On],
+    };
+
+    let buf = value.to_bytes_be().expect("encode failed");
+    assert_eq!(buf.len(), value.encoded_size());
+    assert_eq!(&buf[..], &[0x01, 0xF4, 0x01, 0x02, 0x01, 0x00, 0x02, 0x01]);
+    assert_eq!(
+        Converted::decode_be(&buf, &mut 0).expect("decode failed"),
+        value
+    );
+
+    let mut bad = buf.to_vec();
+    bad[5] = 7;
+    assert_eq!(
+        Converted::decode_be(&bad, &mut 0),
+        Err(serdeb::error::DecodeError::InvalidValue {
+            name: "Mode",
+            value: 7
+        })
+    );
+}
+
+#[derive(Debug, Encoder, Decoder, PartialEq)]
+pub struct WithRuntimeData {
+    #[serdeb(bit_width = 4)]
+    version: u8,
+    #[serdeb(skip)]
+    received: Option<u64>,
+    #[serdeb(bit_width = 4)]
+    flags: u8,
+    len: u8,
+    #[serdeb(skip, default = String::from("unparsed"))]
+    view: String,
+    #[serdeb(len_from = len)]
+    payload: Vec<u8>,
+}
+
+#[test]
+fn skipped_fields() {
+    let value = WithRuntimeData {
+        version: 2,
+        received: Some(1_700_000_000),
+        flags: 0b1010,
+        len: 2,
+        view: "cached".to_string(),
+        payload: vec![0xAA, 0xBB],
+    };
+
+    let buf = value.to_bytes_be().expect("encode failed");
+    assert_eq!(buf.len(), 4);
+    assert_eq!(buf.len(), value.encoded_size());
+    assert_eq!(&buf[1..], &[0x02, 0xAA, 0xBB]);
+
+    let decoded = WithRuntimeData::decode_be(&buf, &mut 0).expect("decode failed");
+    assert_eq!(
+        decoded,
+        WithRuntimeData {
+            received: None,
+            view: "unparsed".to_string(),
+            ..value
+        }
+    );
+}
+
+pub trait Payload: Encode + Decode + EncodedSize {
+    const KIND: u8;
+}
+
+#[derive(Debug, Encoder, Decoder, PartialEq)]
+pub struct Ping {
+    seq: u16,
+}
+
+impl Payload for Ping {
+    const KIND: u8 = 1;
+}
+
+#[derive(Debug, Encoder, Decoder, PartialEq)]
+pub struct Message<P: Payload> {
+    kind: u8,
+    payload: P,
+}
+
+#[derive(Debug, Encoder, Decoder, PartialEq)]
+pub struct Block<const N: usize> {
+    data: [u8; N],
+    words: [u16; N],
+}
+
+#[derive(Debug, Encoder, Decoder, PartialEq)]
+pub struct Envelope<T = u16>
+where
+    T: Copy,
+{
+    count: u8,
+    #[serdeb(len_from = count)]
+    items: Vec<T>,
+    #[serdeb(skip)]
+    marker: core::marker::PhantomData<T>,
+}
+
+#[derive(Debug, Encoder, Decoder, PartialEq)]
+#[serdeb(bound(encode = "M: Into<u8> + Clone", decode = "M: TryFrom<u8>"))]
+pub struct Tagged<M> {
+    #[serdeb(as = u8)]
+    mode: M,
+}
+
+#[test]
+fn generic_structs() {
+    let frame = Message {
+        kind: Ping::KIND,
+        payload: Ping { seq: 7 },
+    };
+    let buf = frame.to_bytes_be().expect("encode failed");
+    assert_eq!(&buf[..], &[0x01, 0x00, 0x07]);
+    assert_eq!(Message::<Ping>::FIXED_SIZE, Some(3));
+    assert_eq!(
+        Message::<Ping>::decode_be(&buf, &mut 0).expect("decode failed"),
+        frame
+    );
+
+    let block = Block::<2> {
+        data: [1, 2],
+        words: [3, 4],
+    };
+    let buf = block.to_bytes_le().expect("encode failed");
+    assert_eq!(&buf[..], &[1, 2, 3, 0, 4, 0]);
+    assert_eq!(Block::<2>::FIXED_SIZE, Some(6));
+    assert_eq!(
+        Block::<2>::decode_le(&buf, &mut 0).expect("decode failed"),
+        block
+    );
+
+    let envelope: Envelope = Envelope {
+        count: 2,
+        items: vec![0x0102, 0x0304],
+        marker: core::marker::PhantomData,
+    };
+    let buf = envelope.to_bytes_be().expect("encode failed");
+    assert_eq!(buf.len(), envelope.encoded_size());
+    assert_eq!(
+        Envelope::decode_be(&buf, &mut 0).expect("decode failed"),
+        envelope
+    );
+
+    let tagged = Tagged { mode: Mode::Auto };
+    let buf = tagged.to_bytes_be().expect("encode failed");
+    assert_eq!(&buf[..], &[0x02]);
+    assert_eq!(
+        Tagged::<Mode>::decode_be(&buf, &mut 0).expect("decode failed"),
+        tagged
+    );
+}
+
+type Words = Vec<u16>;
+type Label = String;
+
+macro_rules! field_type {
+    ($ty:ty) => {
+        #[derive(Debug, Encoder, Decoder, PartialEq)]
+        pub struct Wrapped {
+            #[serdeb(len_prefix = u8)]
+            values: $ty,
+        }
+    };
+}
+
+field_type!(std::vec::Vec<u8>);
+
+#[derive(Debug, Encoder, Decoder, PartialEq)]
+pub struct Qualified {
+    #[serdeb(bit_width = 3)]
+    level: Level,
+    #[serdeb(bit_width = 1)]
+    urgent: bool,
+    #[serdeb(bit_width = 4)]
+    spare: u8,
+    #[serdeb(delimiter = b'\0')]
+    name: std::string::String,
+    #[serdeb(delimiter = b'\0')]
+    label: Label,
+    count: u8,
+    #[serdeb(len_from = count)]
+    raw: std::vec::Vec<u8>,
+    #[serdeb(len_prefix = u8)]
+    words: Words,
+    #[serdeb(len_prefix = u16)]
+    blob: bytes::Bytes,
+    #[serdeb(if = "count > 1", len_from = count)]
+    extra: Option<std::vec::Vec<u8>>,
+    wrapped: self::Wrapped,
+}
+
+/// 声明在使用它的结构体之后
+#[derive(Debug, Encoder, Decoder, Clone, Copy, PartialEq)]
+#[repr(u8)]
+pub enum Level {
+    Low = 1,
+    High = 5,
+}
+
+#[test]
+fn type_path_independent_fields() {
+    let value = Qualified {
+        level: Level::High,
+        urgent: true,
+        spare: 0,
+        name: "ab".to_string(),
+        label: "c".to_string(),
+        count: 2,
+        raw: vec![1, 2],
+        words: vec![0x0304],
+        blob: bytes::Bytes::from_static(&[9]),
+        extra: Some(vec![7, 8]),
+        wrapped: Wrapped { values: vec![6] },
+    };
+
+    let buf = value.to_bytes_be().expect("encode failed");
+    assert_eq!(
+        &buf[..],
+        &[
+            0b0000_1101,
+            b'a',
+            b'b',
+            0,
+            b'c',
+            0,
+            2,
+            1,
+            2,
+            1,
+            0x03,
+            0x04,
+            0x00,
+            0x01,
+            9,
+            7,
+            8,
+            1,
+            6,
+        ][..]
+    );
+    assert_eq!(buf.len(), value.encoded_size());
+    assert_eq!(
+        Qualified::decode_be(&buf, &mut 0).expect("decode failed"),
+        value
+    );
+
+    let mut buf = value.to_bytes_le().expect("encode failed");
+    buf[0] = (buf[0] & !0b111) | 0b010;
+    assert!(matches!(
+        Qualified::decode_le(&buf, &mut 0),
+        Err(serdeb::error::DecodeError::InvalidDiscriminant {
+            name: "Level",
+            value: 2
+        })
+    ));
+}
+
+#[derive(Debug, Encoder, Decoder, PartialEq)]
+pub struct Primitives {
+    big: u128,
+    neg: i128,
+    #[serdeb(bit_width = 100)]
+    huge: u128,
+    #[serdeb(bit_width = 4)]
+    nibble: u8,
+    #[serdeb(bit_width = 21)]
+    initial: char,
+    letter: char,
+    id: std::num::NonZeroU16,
+    counter: std::num::Wrapping<u32>,
+    marker: std::marker::PhantomData<u64>,
+    unit: (),
+    #[serdeb(as = u16)]
+    len: usize,
+    #[serdeb(varint, zigzag)]
+    delta: isize,
+}
+
+#[test]
+fn primitive_coverage() {
+    let value = Primitives {
+        big: u128::MAX - 1,
+        neg: i128::MIN + 1,
+        huge: (1 << 99) | 5,
+        nibble: 0xA,
+        initial: '字',
+        letter: '🦀',
+        id: std::num::NonZeroU16::new(0x1234).unwrap(),
+        counter: std::num::Wrapping(7),
+        marker: std::marker::PhantomData,
+        unit: (),
+        len: 300,
+        delta: -3,
+    };
+
+    for (buf, decode) in [
+        (
+            value.to_bytes_be().expect("encode failed"),
+            Primitives::decode_be as fn(&[u8], &mut usize) -> _,
+        ),
+        (
+            value.to_bytes_le().expect("encode failed"),
+            Primitives::decode_le,
+        ),
+    ] {
+        // 16 + 16 + 16 字节位域 + 4 + 2 + 4 + 2 + 1
+        assert_eq!(buf.len(), 61);
+        assert_eq!(buf.len(), value.encoded_size());
+        assert_eq!(decode(&buf, &mut 0).expect("decode failed"), value);
+    }
+
+    let mut buf = value.to_bytes_be().expect("encode failed");
+    buf[48..52].copy_from_slice(&0xD800u32.to_be_bytes());
+    assert_eq!(
+        Primitives::decode_be(&buf, &mut 0),
+        Err(serdeb::error::DecodeError::InvalidValue {
+            name: "char",
+            value: 0xD800
+        })
+    );
+
+    let mut buf = value.to_bytes_be().expect("encode failed");
+    buf[52..54].fill(0);
+    assert_eq!(
+        Primitives::decode_be(&buf, &mut 0),
+        Err(serdeb::error::DecodeError::InvalidValue {
+            name: "NonZero<u16>",
+            value: 0
+        })
+    );
+
+    let too_wide = Primitives {
+        huge: 1 << 100,
+        ..value
+    };
+    assert!(matches!(
+        too_wide.to_bytes_be(),
+        Err(serdeb::error::EncodeError::BitWidthLimit { field: "huge", .. })
+    ));
+
+    let too_long = Primitives {
+        huge: 0,
+        len: 70000,
+        ..too_wide
+    };
+    assert!(matches!(
+        too_long.to_bytes_le(),
+        Err(serdeb::error::EncodeError::InvalidValue { field: "len" })
+    ));
+}
+
+#[derive(Debug, Encoder, Decoder, PartialEq)]
+pub struct Samples {
+    #[serdeb(bytes = 3)]
+    rate: u32,
+    #[serdeb(bytes = 3)]
+    bias: i32,
+    #[serdeb(bytes = 6)]
+    counter: u64,
+    #[serdeb(bytes = 3)]
+    pair: [u32; 2],
+    count: u8,
+    #[serdeb(len_from = count, bytes = 3)]
+    samples: Vec<i32>,
+    #[serdeb(len_prefix = serdeb::U24)]
+    payload: Vec<u8>,
+    level: serdeb::I24,
+    total: serdeb::U48,
+}
+
+#[test]
+fn narrow_int_fields() {
+    let value = Samples {
+        rate: 0x12_3456,
+        bias: -2,
+        counter: 0x0102_0304_0506,
+        pair: [1, 0xFF_FFFF],
+        count: 2,
+        samples: vec![-1, 0x7F_FFFF],
+        payload: vec![0xAB, 0xCD],
+        level: serdeb::I24::new(-0x80_0000),
+        total: serdeb::U48::new(7),
+    };
+
+    let buf = value.to_bytes_be().expect("encode failed");
+    assert_eq!(buf.len(), value.encoded_size());
+    assert_eq!(
+        &buf[..],
+        &[
+            0x12, 0x34, 0x56, 0xFF, 0xFF, 0xFE, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x00, 0x00,
+            0x01, 0xFF, 0xFF, 0xFF, 0x02, 0xFF, 0xFF, 0xFF, 0x7F, 0xFF, 0xFF, 0x00, 0x00, 0x02,
+            0xAB, 0xCD, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x07,
+        ]
+    );
+    assert_eq!(
+        Samples::decode_be(&buf, &mut 0).expect("decode failed"),
+        value
+    );
+
+    let buf = value.to_bytes_le().expect("encode failed");
+    assert_eq!(&buf[..6], &[0x56, 0x34, 0x12, 0xFE, 0xFF, 0xFF]);
+    assert_eq!(
+        Samples::decode_le(&buf, &mut 0).expect("decode failed"),
+        value
+    );
+
+    let too_wide = Samples {
+        bias: 0x80_0000,
+        ..value
+    };
+    assert!(matches!(
+        too_wide.to_bytes_be(),
+        Err(serdeb::error::EncodeError::WidthOverflow {
+            value: 0x80_0000,
+            bytes: 3
+        })
+    ));
+}
+
+#[derive(Debug, Encoder, Decoder, PartialEq)]
+pub struct Registers {
+    #[serdeb(word_order = "swapped")]
+    voltage: f32,
+    #[serdeb(word_order = "swapped")]
+    energy: u64,
+    #[serdeb(word_order = "swapped")]
+    currents: [i32; 2],
+    count: u8,
+    #[serdeb(len_from = count, word_order = "swapped")]
+    history: Vec<f64>,
+    raw: u32,
+}
+
+#[test]
+fn word_swapped_fields() {
+    let value = Registers {
+        voltage: 1.5,
+        energy: 0x0102_0304_0506_0708,
+        currents: [1, -2],
+        count: 1,
+        history: vec![1.0],
+        raw: 0x1234_5678,
+    };
+
+    let buf = value.to_bytes_be().expect("encode failed");
+    assert_eq!(buf.len(), value.encoded_size());
+    assert_eq!(
+        &buf[..],
+        &[
+            0x00, 0x00, 0x3F, 0xC0, 0x07, 0x08, 0x05, 0x06, 0x03, 0x04, 0x01, 0x02, 0x00, 0x01,
+            0x00, 0x00, 0xFF, 0xFE, 0xFF, 0xFF, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x3F,
+            0xF0, 0x12, 0x34, 0x56, 0x78,
+        ]
+    );
+    assert_eq!(
+        Registers::decode_be(&buf, &mut 0).expect("decode failed"),
+        value
+    );
+
+    let buf = value.to_bytes_le().expect("encode failed");
+    assert_eq!(&buf[..4], &[0xC0, 0x3F, 0x00, 0x00]);
+    assert_eq!(
+        Registers::decode_le(&buf, &mut 0).expect("decode failed"),
+        value
+    );
+}
+
+/// 派生代码不能依赖 prelude, 用户可能定义了同名的类型或函数
+#[allow(dead_code)]
+mod shadowed_prelude {
+    use serdeb::{Decoder, Encoder};
+
+    type Result<T> = core::result::Result<T, ()>;
+    type Option = u8;
+    struct Some;
+    struct None;
+    struct Ok;
+    struct Err;
+    struct Vec;
+    struct String;
+    struct Default;
+    struct Box;
+    struct Into;
+    struct TryFrom;
+    fn size_of() {}
+
+    #[derive(Debug, Encoder, Decoder, Clone, Copy, PartialEq)]
+    #[repr(u8)]
+    pub enum Kind {
+        A = 1,
+        B = 2,
+    }
+
+    #[derive(Debug, Encoder, Decoder, PartialEq)]
+    pub struct Everything {
+        kind: Kind,
+        #[serdeb(bit_width = 3)]
+        bits: u8,
+        #[serdeb(bit_width = 5)]
+        kind_bits: Kind,
+        len: u8,
+        #[serdeb(len_from = len)]
+        items: std::vec::Vec<u16>,
+        #[serdeb(len_prefix = u8)]
+        prefixed: std::vec::Vec<u8>,
+        pair: [u16; 2],
+        #[serdeb(if = "len > 0")]
+        flag: core::option::Option<u8>,
+        #[serdeb(since = 2, default)]
+        v2: u8,
+        #[serdeb(varint)]
+        var: u32,
+        #[serdeb(bytes = 3)]
+        narrow: u32,
+        #[serdeb(as = u16)]
+        wide: u32,
+        #[serdeb(as = u16, scale = 0.5)]
+        scaled: f32,
+        #[serdeb(delimiter = b'\0')]
+        name: std::string::String,
+        #[serdeb(len_of = "items..=name")]
+        body_len: u16,
+        #[serdeb(checksum = "xor8", over = "kind..=name")]
+        sum: u8,
+    }
+
+    #[derive(Debug, Encoder, Decoder, PartialEq)]
+    pub struct Small {
+        a: u8,
+        b: u16,
+    }
+}
@@ -0,0 +1,11 @@
+use serdeb::{Decoder, Encoder};
+
+#[derive(Encoder, Decoder)]
+struct Flags {
+    #[serdeb(bit_width = 4)]
+    name: String,
+    #[serdeb(bit_width = 4)]
+    level: u8,
+}
+
+fn main() {}
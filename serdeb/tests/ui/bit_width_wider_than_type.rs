@@ -0,0 +1,11 @@
+use serdeb::Encoder;
+
+#[derive(Encoder)]
+pub struct Sample {
+    #[serdeb(bit_width = 12)]
+    low: u8,
+    #[serdeb(bit_width = 4)]
+    high: u8,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use serdeb::Encoder;
+
+#[derive(Encoder)]
+pub struct Sample {
+    #[serdeb(bytes = 3)]
+    value: u16,
+}
+
+fn main() {}
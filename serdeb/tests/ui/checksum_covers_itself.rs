@@ -0,0 +1,10 @@
+use serdeb::{Decoder, Encoder};
+
+#[derive(Encoder, Decoder)]
+struct Frame {
+    a: u8,
+    #[serdeb(checksum = "crc16_modbus", over = "a..=crc")]
+    crc: u16,
+}
+
+fn main() {}
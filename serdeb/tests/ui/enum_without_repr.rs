@@ -0,0 +1,9 @@
+use serdeb::{Decoder, Encoder};
+
+#[derive(Encoder, Decoder)]
+enum Kind {
+    A = 1,
+    B = 2,
+}
+
+fn main() {}
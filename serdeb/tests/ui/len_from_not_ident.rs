@@ -0,0 +1,10 @@
+use serdeb::{Decoder, Encoder};
+
+#[derive(Encoder, Decoder)]
+struct Items {
+    len: u8,
+    #[serdeb(len_from = "len")]
+    items: Vec<u16>,
+}
+
+fn main() {}
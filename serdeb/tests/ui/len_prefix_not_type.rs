@@ -0,0 +1,11 @@
+use serdeb::Encoder;
+
+#[derive(Encoder)]
+pub struct Sample {
+    #[serdeb(len_prefix = 1 + 2)]
+    items: Vec<u8>,
+    #[serdeb(as = 3)]
+    value: u8,
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use serdeb::Decoder;
+
+#[derive(Decoder)]
+pub struct Record {
+    #[serdeb(len_prefix = u8)]
+    raw: [u8; 4],
+    #[serdeb(len_prefix = u8)]
+    name: String,
+    pairs: [Vec<u8>; 2],
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+use serdeb::{Decoder, Encoder};
+
+#[derive(Encoder, Decoder)]
+struct Packet {
+    #[serdeb(zigzag)]
+    id: i32,
+    #[serdeb(delimiter = "abc")]
+    name: String,
+    #[serdeb(len_from = count)]
+    data: Vec<u8>,
+}
+
+fn main() {}
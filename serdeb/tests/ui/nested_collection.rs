@@ -0,0 +1,10 @@
+use serdeb::{Decoder, Encoder};
+
+#[derive(Encoder, Decoder)]
+pub struct Sample {
+    rows: u8,
+    #[serdeb(len_from = rows)]
+    matrix: Vec<Vec<u8>>,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use serdeb::{Decoder, Encoder};
+
+#[derive(Encoder, Decoder)]
+union Raw {
+    int: u32,
+    float: f32,
+}
+
+fn main() {}
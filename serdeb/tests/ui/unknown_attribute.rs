@@ -0,0 +1,9 @@
+use serdeb::{Decoder, Encoder};
+
+#[derive(Encoder, Decoder)]
+struct Header {
+    #[serdeb(bitwidth = 3)]
+    kind: u8,
+}
+
+fn main() {}
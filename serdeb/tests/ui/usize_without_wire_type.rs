@@ -0,0 +1,10 @@
+use serdeb::{Decoder, Encoder};
+
+#[derive(Encoder, Decoder)]
+struct Header {
+    len: usize,
+    #[serdeb(len_prefix = u8)]
+    offsets: Vec<isize>,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use serdeb::{Decoder, Encoder};
+
+#[derive(Encoder, Decoder)]
+struct Items {
+    len: u8,
+    items: Vec<u16>,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use serdeb::Encoder;
+
+#[derive(Encoder)]
+pub struct Registers {
+    #[serdeb(word_order = "swapped")]
+    value: u16,
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+use core::num::Wrapping;
+
+use serdeb::Encoder;
+
+#[derive(Encoder)]
+pub struct Registers {
+    #[serdeb(word_order = "swapped")]
+    counter: Wrapping<u32>,
+    #[serdeb(word_order = "swapped", as = u32)]
+    total: u64,
+}
+
+fn main() {}
@@ -3,85 +3,177 @@ use quote::quote;
 
 use crate::*;
 
+/// 生成的是哪一组解码函数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DecodeMode {
+    Plain,
+    /// `decode_versioned_*`, 按版本号跳过字段
+    Versioned,
+    /// `decode_partial_*`, 数据可能还没到齐, 数据用完时不取默认值
+    Partial,
+    /// `decode_source_*`, 嵌套的解码都带着 `__serdeb_source`
+    Source,
+}
+
 pub fn decode_input(input: TokenStream) -> TokenStream {
     let input = syn::parse_macro_input!(input as syn::DeriveInput);
     match &input.data {
         syn::Data::Struct(_) => {
             let struct_name = &input.ident;
-            let generics = &input.generics;
 
-            let struct_info = Arc::new(StructInfo::parse(&input));
-            let _ = STRUCT_MAP
-                .lock()
-                .unwrap()
-                .insert(struct_name.to_string(), struct_info.clone());
+            let struct_info = match StructInfo::parse(&input) {
+                Ok(struct_info) => struct_info,
+                Err(err) => return err.to_compile_error().into(),
+            };
 
             let (stmts_be, field_inits_be) =
-                decode_struct(&input, ByteOrder::BE, &struct_info);
+                decode_struct(&input, ByteOrder::BE, &struct_info, DecodeMode::Plain);
             let (stmts_le, field_inits_le) =
-                decode_struct(&input, ByteOrder::LE, &struct_info);
+                decode_struct(&input, ByteOrder::LE, &struct_info, DecodeMode::Plain);
+            let (stmts_versioned_be, field_inits_versioned_be) =
+                decode_struct(&input, ByteOrder::BE, &struct_info, DecodeMode::Versioned);
+            let (stmts_versioned_le, field_inits_versioned_le) =
+                decode_struct(&input, ByteOrder::LE, &struct_info, DecodeMode::Versioned);
+            let (stmts_partial_be, field_inits_partial_be) =
+                decode_struct(&input, ByteOrder::BE, &struct_info, DecodeMode::Partial);
+            let (stmts_partial_le, field_inits_partial_le) =
+                decode_struct(&input, ByteOrder::LE, &struct_info, DecodeMode::Partial);
+            let (stmts_source_be, field_inits_source_be) =
+                decode_struct(&input, ByteOrder::BE, &struct_info, DecodeMode::Source);
+            let (stmts_source_le, field_inits_source_le) =
+                decode_struct(&input, ByteOrder::LE, &struct_info, DecodeMode::Source);
+            let (min_size, reads_to_end) = gen_decode_consts(&input, &struct_info);
+            let generics = struct_info.impl_generics(&input, quote! { ::serdeb::Decode }, false);
+            let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
             quote! {
-                impl #generics ::serdeb::Decode for #struct_name #generics {
-                    fn decode_be(buf: &[u8], offset: &mut usize) -> ::std::result::Result<Self, ()> {
-                        use ::bytes::Buf;
+                impl #impl_generics ::serdeb::Decode for #struct_name #ty_generics #where_clause {
+                    const MIN_SIZE: usize = #min_size;
+                    const READS_TO_END: bool = #reads_to_end;
+
+                    fn decode_be(buf: &[u8], offset: &mut usize) -> ::core::result::Result<Self, ::serdeb::error::DecodeError> {
 
                         #(#stmts_be)*
 
-                        Ok(Self {
+                        ::core::result::Result::Ok(Self {
                             #(#field_inits_be),*
                         })
                     }
 
-                    fn decode_le(buf: &[u8], offset: &mut usize) -> ::std::result::Result<Self, ()> {
-                        use ::bytes::Buf;
+                    fn decode_le(buf: &[u8], offset: &mut usize) -> ::core::result::Result<Self, ::serdeb::error::DecodeError> {
 
                         #(#stmts_le)*
 
-                        Ok(Self {
+                        ::core::result::Result::Ok(Self {
                             #(#field_inits_le),*
                         })
                     }
+
+                    fn decode_versioned_be(buf: &[u8], offset: &mut usize, __version: u32) -> ::core::result::Result<Self, ::serdeb::error::DecodeError> {
+                        #(#stmts_versioned_be)*
+
+                        ::core::result::Result::Ok(Self {
+                            #(#field_inits_versioned_be),*
+                        })
+                    }
+
+                    fn decode_versioned_le(buf: &[u8], offset: &mut usize, __version: u32) -> ::core::result::Result<Self, ::serdeb::error::DecodeError> {
+                        #(#stmts_versioned_le)*
+
+                        ::core::result::Result::Ok(Self {
+                            #(#field_inits_versioned_le),*
+                        })
+                    }
+
+                    fn decode_partial_be(buf: &[u8], offset: &mut usize) -> ::core::result::Result<Self, ::serdeb::error::DecodeError> {
+                        ::serdeb::__private::decode_partial(buf, offset, |buf, offset| {
+                            #(#stmts_partial_be)*
+
+                            ::core::result::Result::Ok(Self {
+                                #(#field_inits_partial_be),*
+                            })
+                        })
+                    }
+
+                    fn decode_partial_le(buf: &[u8], offset: &mut usize) -> ::core::result::Result<Self, ::serdeb::error::DecodeError> {
+                        ::serdeb::__private::decode_partial(buf, offset, |buf, offset| {
+                            #(#stmts_partial_le)*
+
+                            ::core::result::Result::Ok(Self {
+                                #(#field_inits_partial_le),*
+                            })
+                        })
+                    }
+
+                    fn decode_source_be(__serdeb_source: ::serdeb::DecodeSource<'_>, offset: &mut usize) -> ::core::result::Result<Self, ::serdeb::error::DecodeError> {
+                        let buf = __serdeb_source.as_slice();
+                        #(#stmts_source_be)*
+
+                        ::core::result::Result::Ok(Self {
+                            #(#field_inits_source_be),*
+                        })
+                    }
+
+                    fn decode_source_le(__serdeb_source: ::serdeb::DecodeSource<'_>, offset: &mut usize) -> ::core::result::Result<Self, ::serdeb::error::DecodeError> {
+                        let buf = __serdeb_source.as_slice();
+                        #(#stmts_source_le)*
+
+                        ::core::result::Result::Ok(Self {
+                            #(#field_inits_source_le),*
+                        })
+                    }
                 }
             }
-            .into()
         }
         syn::Data::Enum(_) => {
             let enum_name = &input.ident;
-            let enum_info = Arc::new(EnumInfo::parse(&input));
-            let _ = ENUM_MAP
-                .lock()
-                .unwrap()
-                .insert(enum_name.to_string(), enum_info.clone());
+            let enum_info = match EnumInfo::parse(&input) {
+                Ok(enum_info) => enum_info,
+                Err(err) => return err.to_compile_error().into(),
+            };
 
             let stmts_be =
                 decode_enum(&input, ByteOrder::BE, &enum_info);
             let stmts_le =
                 decode_enum(&input, ByteOrder::LE, &enum_info);
 
-            let generics = &input.generics;
+            let repr_ty = str_to_type(&enum_info.repr_ty);
+            let enum_match_stmt = gen_enum_match(enum_name, &enum_info);
+
+            let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
             quote! {
-                impl #generics ::serdeb::Decode for #enum_name #generics {
-                    fn decode_be(buf: &[u8], offset: &mut usize) -> ::std::result::Result<Self, ()> {
-                        use ::bytes::Buf;
-        
+                impl #impl_generics ::serdeb::DecodeBits for #enum_name #ty_generics #where_clause {
+                    type Raw = #repr_ty;
+
+                    fn from_bits(v: #repr_ty) -> ::core::result::Result<Self, ::serdeb::error::DecodeError> {
+                        #enum_match_stmt
+                    }
+                }
+
+                impl #impl_generics ::serdeb::Decode for #enum_name #ty_generics #where_clause {
+                    const MIN_SIZE: usize = <#repr_ty as ::serdeb::Decode>::MIN_SIZE;
+
+                    fn decode_be(buf: &[u8], offset: &mut usize) -> ::core::result::Result<Self, ::serdeb::error::DecodeError> {
+
                         #(#stmts_be)*
-        
+
                         // 最终返回枚举值 v
-                        Ok(v)
+                        ::core::result::Result::Ok(v)
                     }
 
-                    fn decode_le(buf: &[u8], offset: &mut usize) -> ::std::result::Result<Self, ()> {
-                        use ::bytes::Buf;
-        
+                    fn decode_le(buf: &[u8], offset: &mut usize) -> ::core::result::Result<Self, ::serdeb::error::DecodeError> {
+
                         #(#stmts_le)*
-        
+
                         // 最终返回枚举值 v
-                        Ok(v)
+                        ::core::result::Result::Ok(v)
                     }
                 }
             }
         },
-        syn::Data::Union(_) => todo!(),
+        syn::Data::Union(data_union) => {
+            syn::Error::new_spanned(data_union.union_token, "serdeb does not support unions")
+                .to_compile_error()
+        }
     }
     .into()
 }
@@ -90,6 +182,7 @@ fn decode_struct(
     input: &syn::DeriveInput,
     byte_order: ByteOrder,
     struct_info: &StructInfo,
+    mode: DecodeMode,
 ) -> (Vec<proc_macro2::TokenStream>, Vec<proc_macro2::TokenStream>) {
     let mut decode_stmts: Vec<proc_macro2::TokenStream> = Vec::new();
     let mut field_inits: Vec<proc_macro2::TokenStream> = Vec::new();
@@ -100,6 +193,10 @@ fn decode_struct(
 
     let mut decode_stmts_inner: Vec<proc_macro2::TokenStream> = Vec::new();
 
+    if struct_info.has_computed {
+        decode_stmts.push(quote! { let __serdeb_start = *offset; });
+    }
+
     if let syn::Fields::Named(fields) = &data_struct.fields {
         let mut field_index = 0;
         let mut bitfield_section_index = 0;
@@ -116,38 +213,79 @@ fn decode_struct(
                 let section_start = section[0];
                 let section_end = section[1];
                 let mut bit_offset = 0;
+                let mut section_has_default = false;
+
+                let section_names: Vec<Ident> = (section_start..section_end)
+                    .map(|i| fields.named[i].ident.clone().unwrap())
+                    .collect();
+
+                if struct_info.has_computed {
+                    for name in &section_names {
+                        let (start, _) = field_position_idents(name);
+                        decode_stmts.push(quote! { let #start = *offset; });
+                    }
+                }
 
                 for field_index in section_start..section_end {
                     let field = &fields.named[field_index];
                     let name = field.ident.clone().unwrap();
                     let name_str = name.to_string();
-                    let mut primitive_ty = field.ty.clone();
-                    let primitive_ty_str = primitive_ty.to_token_stream().to_string();
+                    let field_ty = &field.ty;
+                    let mut primitive_ty: Type =
+                        syn::parse_quote! { <#field_ty as ::serdeb::DecodeBits>::Raw };
 
                     let field_info = &struct_info.fields[&name_str];
-                    let use_default = field_info.use_default;
-                    let default_value = &field_info.default_value;
+                    if field_info.skip {
+                        let default_value = field_info.default_expr(field_ty);
+                        decode_stmts.push(quote! { let #name = #default_value; });
+                        field_inits.push(quote! { #name });
+                        continue;
+                    }
+
+                    // 数据可能还没到齐时不能认为整个位域段缺失
+                    let use_default = field_info.use_default && mode != DecodeMode::Partial;
                     let bit_width = field_info.bit_width.unwrap();
+                    let byte_end = match byte_order {
+                        ByteOrder::BE => (bit_offset + bit_width).div_ceil(8),
+                        ByteOrder::LE => byte_len,
+                    };
 
                     decode_stmts_inner.clear();
 
-                    let enum_match_stmt = if is_enum_type(&primitive_ty_str) {
-                        let enum_info = get_enum_info(&primitive_ty_str).unwrap();
-                        primitive_ty = str_to_type(&enum_info.repr_ty);
+                    let mut from_bits_stmt = quote! {
+                        let v = match <#field_ty as ::serdeb::DecodeBits>::from_bits(v) {
+                            ::core::result::Result::Ok(v) => v,
+                            ::core::result::Result::Err(e) => return ::core::result::Result::Err(e),
+                        };
+                    };
 
-                        let enum_info = get_enum_info(&primitive_ty_str).unwrap();
-                        let enum_ident = Ident::new(&primitive_ty_str, Span::call_site());
+                    // 定点数先按原始整数解出位域, 再换算成浮点数
+                    let fixed_point_stmt = match field_info.fixed_point() {
+                        Some((raw_ty, scale, offset)) => {
+                            primitive_ty = raw_ty;
+                            from_bits_stmt = quote! {};
+                            let ty = &field.ty;
+                            quote! {
+                                let v = ::serdeb::__private::from_fixed(v, #scale, #offset) as #ty;
+                            }
+                        }
+                        None => quote! {},
+                    };
 
-                        let stmt = gen_enum_match(&enum_ident, &enum_info);
-                        quote! { 
-                            let res = { #stmt };
-                            let v = match res {
-                                Ok(v) => v,
-                                Err(_) => return Err(()),
-                            };
+                    let wire_conversion_stmt = match field_info.wire_conversion() {
+                        Some(wire_ty) => {
+                            primitive_ty = wire_ty.clone();
+                            let ty = &field.ty;
+                            let try_from = gen_try_from_wire(ty, &wire_ty);
+                            from_bits_stmt = quote! {};
+                            quote! {
+                                let v = match #try_from {
+                                    ::core::result::Result::Ok(v) => v,
+                                    ::core::result::Result::Err(e) => return ::core::result::Result::Err(e),
+                                };
+                            }
                         }
-                    } else {
-                        quote! {}
+                        None => quote! {},
                     };
 
                     let shift_decode_stmt = match byte_order {
@@ -155,54 +293,51 @@ fn decode_struct(
                         ByteOrder::LE => quote! { i },
                     };
 
+                    // 与编码一致, 小端时整个位域段的字节顺序翻转
+                    let byte_index_stmt = match byte_order {
+                        ByteOrder::BE => quote! { bit_index / 8 },
+                        ByteOrder::LE => quote! { #byte_len - 1 - bit_index / 8 },
+                    };
+
                     let no_checked_decode_stmt = quote! {
-                        let mut v = #primitive_ty::default();
+                        let mut v = <#primitive_ty as ::core::default::Default>::default();
 
                         for i in 0..#bit_width {
                             let bit_index = #bit_offset + i;
-                            let byte_index = *offset + bit_index / 8;
+                            let byte_index = *offset + #byte_index_stmt;
                             let byte = buf[byte_index];
                             let bit = (byte >> (bit_index % 8)) & 1;
 
                             if bit != 0 {
-                                v |= #primitive_ty::from(1u8) << #shift_decode_stmt;
+                                v |= <#primitive_ty as ::core::convert::From<u8>>::from(1u8) << #shift_decode_stmt;
                             }
                         }
                     };
 
-                    if use_default {
-                        match default_value {
-                            None => decode_stmts_inner.push(quote! {
-                                if *offset + (#bit_offset + #bit_width + 7) / 8 > buf.len() {
-                                    #primitive_ty::default()
-                                } else {
-                                    #no_checked_decode_stmt
-                                    #enum_match_stmt
-                                    v
-                                }
-                            }),
-                            Some(value) => {
-                                let ident: Ident = syn::Ident::new(&value, Span::call_site());
-                                decode_stmts_inner.push(quote! {
-                                    if *offset + (#bit_offset + #bit_width + 7) / 8 > buf.len() {
-                                        #ident
-                                    } else {
-                                        #no_checked_decode_stmt
-                                        #enum_match_stmt
-                                        v
-                                    }
-                                });
-                            }
+                    let decode_bits_stmt = quote! {
+                        if *offset + #byte_end > buf.len() {
+                            return ::core::result::Result::Err(::serdeb::error::DecodeError::UnexpectedEof { needed: ::core::option::Option::Some(*offset + #byte_end - buf.len()) });
                         }
-                    } else {
+                        #no_checked_decode_stmt
+                        #from_bits_stmt
+                        #fixed_point_stmt
+                        #wire_conversion_stmt
+                        v
+                    };
+
+                    // 只有整个位域段都不在输入中时才取默认值, 只到了一部分仍然按数据不足处理
+                    if use_default {
+                        section_has_default = true;
+                        let default_value = field_info.default_expr(field_ty);
                         decode_stmts_inner.push(quote! {
-                            if *offset + (#bit_offset + #bit_width + 7) / 8 > buf.len() {
-                                return Err(());
+                            if *offset >= buf.len() {
+                                #default_value
+                            } else {
+                                #decode_bits_stmt
                             }
-                            #no_checked_decode_stmt
-                            #enum_match_stmt
-                            v
                         });
+                    } else {
+                        decode_stmts_inner.push(decode_bits_stmt);
                     };
 
                     decode_stmts.push(quote! {
@@ -213,9 +348,29 @@ fn decode_struct(
                     bit_offset += bit_width;
                 }
 
-                decode_stmts.push(quote! {
-                    *offset += #byte_len;
-                });
+                // 整个位域段都取了默认值时不越过输入的结尾
+                if section_has_default {
+                    decode_stmts.push(quote! {
+                        *offset = ::core::cmp::min(*offset + #byte_len, buf.len());
+                    });
+                } else {
+                    decode_stmts.push(quote! {
+                        *offset += #byte_len;
+                    });
+                }
+
+                if struct_info.has_computed {
+                    for name in &section_names {
+                        let (_, end) = field_position_idents(name);
+                        decode_stmts.push(quote! { let #end = *offset; });
+                    }
+                }
+
+                for (field, field_info) in
+                    struct_info.computed_fields_after(fields, section_start..section_end)
+                {
+                    decode_stmts.push(gen_checksum_verify(field, field_info));
+                }
 
                 field_index = section_end;
                 bitfield_section_index += 1;
@@ -227,42 +382,90 @@ fn decode_struct(
                 let name_str = name.to_string();
                 let ty = &field.ty;
                 let field_info = &struct_info.fields[&name_str];
-                let use_default = field_info.use_default;
-                let default_value = &field_info.default_value;
+                if field_info.skip {
+                    let default_value = field_info.default_expr(ty);
+                    decode_stmts.push(quote! { let #name = #default_value; });
+                    field_inits.push(quote! { #name });
+                    field_index += 1;
+                    continue;
+                }
+
+                // 数据可能还没到齐时不能认为字段缺失
+                let use_default = field_info.use_default && mode != DecodeMode::Partial;
 
-                let decode_data_stmt = gen_decode_data(ty, byte_order, field_info);
+                // 条件字段为 Option<T> 时, 条件成立则按 T 解码
+                let option_inner_ty = field_info
+                    .condition
+                    .as_ref()
+                    .and_then(|_| option_inner_type(ty));
+
+                let decode_data_stmt =
+                    gen_decode_data(option_inner_ty.unwrap_or(ty), byte_order, field_info, mode);
                 decode_stmts_inner.push(quote! {
                     let res = { #decode_data_stmt };
                 });
 
-                let default_value_stmt = match default_value {
-                    Some(v) => {
-                        let ident = Ident::new(v, Span::call_site());
-                        quote! { #ident }
-                    }
-                    None => quote! {
-                        let v: #ty = Default::default();
-                        v
-                    },
-                };
-                decode_stmts_inner.push(quote! {
-                    match res {
-                        Ok(v) => v,
-                        Err(_) => {
-                            if #use_default {
-                                #default_value_stmt
-                            } else {
-                                return Err(());
-                            }
+                if option_inner_ty.is_some() {
+                    decode_stmts_inner.push(quote! {
+                        let res = res.map(::core::option::Option::Some);
+                    });
+                }
+
+                decode_stmts_inner.push(quote! { res? });
+
+                let default_value_stmt = field_info.default_expr(ty);
+                // 只有输入在字段开始处结束时才取默认值, 其他错误照常返回;
+                // 只有 use_default 时才要求字段类型实现 Default
+                if use_default {
+                    let inner = core::mem::take(&mut decode_stmts_inner);
+                    decode_stmts_inner.push(quote! {
+                        if *offset >= buf.len() {
+                            #default_value_stmt
+                        } else {
+                            #(#inner)*
                         }
-                    }
-                });
+                    });
+                }
 
-                decode_stmts.push(quote! {
-                    let #name = { #(#decode_stmts_inner)* };
-                });
+                let (start, end) = field_position_idents(&name);
+                if struct_info.has_computed {
+                    decode_stmts.push(quote! { let #start = *offset; });
+                }
+
+                let mut guards = Vec::new();
+                if let Some(condition) = &field_info.condition {
+                    guards.push(quote! { #condition });
+                }
+                if mode == DecodeMode::Versioned
+                    && let Some(version_guard) = field_info.version_guard()
+                {
+                    guards.push(version_guard);
+                }
+
+                match join_guards(guards) {
+                    Some(guard) => decode_stmts.push(quote! {
+                        let #name = if #guard {
+                            #(#decode_stmts_inner)*
+                        } else {
+                            #default_value_stmt
+                        };
+                    }),
+                    None => decode_stmts.push(quote! {
+                        let #name = { #(#decode_stmts_inner)* };
+                    }),
+                }
                 field_inits.push(quote! { #name });
 
+                if struct_info.has_computed {
+                    decode_stmts.push(quote! { let #end = *offset; });
+                }
+
+                for (field, field_info) in
+                    struct_info.computed_fields_after(fields, field_index..field_index + 1)
+                {
+                    decode_stmts.push(gen_checksum_verify(field, field_info));
+                }
+
                 field_index += 1;
             }
         }
@@ -271,6 +474,33 @@ fn decode_struct(
     (decode_stmts, field_inits)
 }
 
+fn gen_checksum_verify(field: &syn::Field, field_info: &FieldInfo) -> proc_macro2::TokenStream {
+    let Some(ComputedField::Checksum(checksum)) = &field_info.computed else {
+        return quote! {};
+    };
+
+    let name = field.ident.clone().unwrap();
+    let name_str = name.to_string();
+    let ty = &field.ty;
+    let [start, end] = field_info
+        .range
+        .as_ref()
+        .unwrap()
+        .clone()
+        .map(|v| Ident::new(&v, Span::call_site()));
+
+    quote! {
+        let __serdeb_computed = <#checksum as ::serdeb::checksum::Checksum>::checksum(&buf[#start..#end]) as #ty;
+        if #name != __serdeb_computed {
+            return ::core::result::Result::Err(::serdeb::error::DecodeError::ChecksumMismatch {
+                field: #name_str,
+                expected: #name as u64,
+                computed: __serdeb_computed as u64,
+            });
+        }
+    }
+}
+
 fn decode_enum(
     input: &syn::DeriveInput,
     byte_order: ByteOrder,
@@ -280,23 +510,23 @@ fn decode_enum(
     let mut decode_stmts_inner: Vec<proc_macro2::TokenStream> = Vec::new();
 
     let repr_ty = str_to_type(&enum_info.repr_ty);
-    let stmt =  match byte_order {
-        ByteOrder::BE => quote! { #repr_ty::decode_be(buf, offset) },
-        ByteOrder::LE => quote! { #repr_ty::decode_le(buf, offset) },
+    let stmt = match byte_order {
+        ByteOrder::BE => quote! { <#repr_ty as ::serdeb::Decode>::decode_be(buf, offset) },
+        ByteOrder::LE => quote! { <#repr_ty as ::serdeb::Decode>::decode_le(buf, offset) },
     };
 
-    let enum_match_stmt = gen_enum_match(&input.ident, &enum_info);
+    let enum_match_stmt = gen_enum_match(&input.ident, enum_info);
     decode_stmts_inner.push(quote! {
         let res = { #stmt };
         match res {
-            Ok(v) => {
+            ::core::result::Result::Ok(v) => {
                 let res = { #enum_match_stmt };
                 match res {
-                    Ok(v) => v,
-                    Err(_) => return Err(()),
+                    ::core::result::Result::Ok(v) => v,
+                    ::core::result::Result::Err(e) => return ::core::result::Result::Err(e),
                 }
             }
-            Err(_) => return Err(())
+            ::core::result::Result::Err(e) => return ::core::result::Result::Err(e)
         }
     });
 
@@ -311,27 +541,31 @@ fn gen_enum_match(enum_ident: &syn::Ident, enum_info: &EnumInfo) -> proc_macro2:
     let mut stmts = Vec::new();
 
     let primitive_ty = str_to_type(&enum_info.repr_ty);
+    let enum_name = enum_ident.to_string();
 
     for (ident, discr) in enum_info.variants.iter() {
-        let ident = Ident::new(&ident, Span::call_site());
+        let ident = Ident::new(ident, Span::call_site());
 
         let value_expr = match discr {
             Some(expr) => {
-                let expr = syn::LitInt::new(&expr, Span::call_site());
+                let expr = syn::LitInt::new(expr, Span::call_site());
                 quote! { (#expr) as #primitive_ty }
             }
             None => quote! { (#enum_ident::#ident as #primitive_ty) },
         };
 
         stmts.push(quote! {
-            x if x == #value_expr => Ok(#enum_ident::#ident),
+            x if x == #value_expr => ::core::result::Result::Ok(#enum_ident::#ident),
         });
     }
 
     quote! {
         match v {
             #(#stmts)*
-            _ => Err(()),
+            _ => ::core::result::Result::Err(::serdeb::error::DecodeError::InvalidDiscriminant {
+                name: #enum_name,
+                value: v as i128,
+            }),
         }
     }
 }
@@ -340,110 +574,318 @@ fn gen_decode_data(
     ty: &syn::Type,
     byte_order: ByteOrder,
     field_info: &FieldInfo,
+    mode: DecodeMode,
 ) -> proc_macro2::TokenStream {
-    let ty_str = ty.to_token_stream().to_string();
+    if let Some(decode_with) = &field_info.decode_with {
+        let endian = endian_tokens(byte_order);
+        let context = field_info.context_arg(|name| quote! { #name });
+        return quote! { #decode_with(buf, offset, #endian #context) };
+    }
+
+    if let Some((raw_ty, scale, offset)) = field_info.fixed_point() {
+        let raw = match byte_order {
+            ByteOrder::BE => quote! { <#raw_ty as ::serdeb::Decode>::decode_be(buf, offset) },
+            ByteOrder::LE => quote! { <#raw_ty as ::serdeb::Decode>::decode_le(buf, offset) },
+        };
+        return quote! {
+            #raw.map(|v| ::serdeb::__private::from_fixed(v, #scale, #offset) as #ty)
+        };
+    }
 
-    if is_struct_type(&ty_str) || is_enum_type(&ty_str) {
-        match byte_order {
-            ByteOrder::BE => quote! { #ty::decode_be(buf, offset) },
-            ByteOrder::LE => quote! { #ty::decode_le(buf, offset) },
+    if let Some(wire_ty) = field_info.wire_conversion()
+        && list_elem_type(ty).is_none()
+    {
+        let raw = match byte_order {
+            ByteOrder::BE => quote! { <#wire_ty as ::serdeb::Decode>::decode_be(buf, offset) },
+            ByteOrder::LE => quote! { <#wire_ty as ::serdeb::Decode>::decode_le(buf, offset) },
+        };
+        let try_from = gen_try_from_wire(ty, &wire_ty);
+        return quote! { #raw.and_then(|v| #try_from) };
+    }
+
+    gen_decode_std_data(ty, byte_order, field_info, mode)
+}
+
+/// 通过 `Decode` 解码, 版本化解码时传入版本号, 数据可能不全时嵌套调用 `decode_partial_*`
+fn gen_decode_trait(
+    ty: &syn::Type,
+    byte_order: ByteOrder,
+    mode: DecodeMode,
+) -> proc_macro2::TokenStream {
+    match (byte_order, mode) {
+        (ByteOrder::BE, DecodeMode::Plain) => {
+            quote! { <#ty as ::serdeb::Decode>::decode_be(buf, offset) }
+        }
+        (ByteOrder::LE, DecodeMode::Plain) => {
+            quote! { <#ty as ::serdeb::Decode>::decode_le(buf, offset) }
+        }
+        (ByteOrder::BE, DecodeMode::Versioned) => {
+            quote! { <#ty as ::serdeb::Decode>::decode_versioned_be(buf, offset, __version) }
+        }
+        (ByteOrder::LE, DecodeMode::Versioned) => {
+            quote! { <#ty as ::serdeb::Decode>::decode_versioned_le(buf, offset, __version) }
+        }
+        (ByteOrder::BE, DecodeMode::Partial) => {
+            quote! { <#ty as ::serdeb::Decode>::decode_partial_be(buf, offset) }
+        }
+        (ByteOrder::LE, DecodeMode::Partial) => {
+            quote! { <#ty as ::serdeb::Decode>::decode_partial_le(buf, offset) }
+        }
+        (ByteOrder::BE, DecodeMode::Source) => {
+            quote! { <#ty as ::serdeb::Decode>::decode_source_be(__serdeb_source, offset) }
+        }
+        (ByteOrder::LE, DecodeMode::Source) => {
+            quote! { <#ty as ::serdeb::Decode>::decode_source_le(__serdeb_source, offset) }
         }
-    } else {
-        let decode_stmt = gen_decode_std_data(ty, byte_order, &field_info);
-        quote! { #decode_stmt }
     }
 }
 
+/// 只有语法糖 (定长数组, varint, 字符串, 已知集合) 需要按类型展开, 其余类型都通过 trait 解码
 fn gen_decode_std_data(
     ty: &syn::Type,
     byte_order: ByteOrder,
     field_info: &FieldInfo,
+    mode: DecodeMode,
 ) -> proc_macro2::TokenStream {
-    match &ty {
-        Type::Array(type_array) => {
-            let elem = &type_array.elem;
-            let len = &type_array.len;
-
-            if is_fixed_primitive_type(&elem) {
-                match byte_order {
-                    ByteOrder::BE => quote! { #ty::decode_be(buf, offset) },
-                    ByteOrder::LE => quote! { #ty::decode_le(buf, offset) },
-                }
-            } else {
-                let elem_decode_stmt = gen_decode_data(&elem, byte_order, field_info);
+    let has_len = field_info.len_from.is_some() || field_info.len_prefix.is_some();
 
-                quote! {
-                    let mut list = with_capacity(#len);
-                    let mut err_index = None;
+    if let Type::Array(type_array) = ungroup(ty) {
+        let elem = &type_array.elem;
+        let len = &type_array.len;
 
-                    for i in 0..#len {
-                        let res = #elem_decode_stmt;
-                        match res {
-                            Ok(v) => list.push(v),
-                            Err(_) => { err_index.get_or_insert(i); }
-                        }
-                    }
+        if is_fixed_primitive_type(elem)
+            && field_info.int_wrapper(elem).is_none()
+            && field_info.wire_conversion().is_none()
+        {
+            return gen_decode_trait(ty, byte_order, DecodeMode::Plain);
+        }
 
-                    if let Some(err_index) = err_index {
-                        Err(())
-                    } else {
-                        Ok(list)
-                    }
-                }
+        let elem_decode_stmt = gen_decode_data(elem, byte_order, field_info, mode);
+        return quote! {
+            ::serdeb::__private::decode_array::<_, { #len }>(|| { #elem_decode_stmt })
+        };
+    }
+
+    if (is_fixed_primitive_type(ty) || contains_platform_int_type(ty))
+        && let Some(wrapper) = field_info.int_wrapper(ty)
+    {
+        return match byte_order {
+            ByteOrder::BE => {
+                quote! { <#wrapper as ::serdeb::Decode>::decode_be(buf, offset).map(|v| v.0) }
             }
-        }
-        Type::Path(type_path) => {
-            let ident = type_path.path.segments.last().unwrap().ident.to_string();
-            if is_fixed_primitive_type_str(&ident) {
-                match byte_order {
-                    ByteOrder::BE => quote! { #ty::decode_be(buf, offset) },
-                    ByteOrder::LE => quote! { #ty::decode_le(buf, offset) },
-                }
-            } else if is_str_type(&ident) {
-                match &field_info.str_delimiter {
-                    Some(str_delimiter) => {
-                        let str_delimiter = proc_macro2::Literal::byte_string(str_delimiter.as_slice());
-                        quote! { #ty::decode_str(buf, offset, Some(#str_delimiter)) }
-                    },
-                    None => quote! { #ty::decode_str(buf, offset, None) },
-                }
-            } else if is_dynamic_list_type(&ident) {
-                let len_from_ident = Ident::new(
-                    field_info.len_from.as_ref().expect("Vec need len_from"),
-                    Span::call_site(),
-                );
-
-                let seg = type_path.path.segments.last().unwrap();
-                if let syn::PathArguments::AngleBracketed(args) = &seg.arguments {
-                    if let Some(syn::GenericArgument::Type(inner_ty)) = args.args.first() {
-                        let stmt = gen_decode_data(inner_ty, byte_order, field_info);
-                        return quote! {
-                            let len = #len_from_ident as usize;
-                            let mut list = Vec::with_capacity(len);
-                            let mut err_index = None;
-
-                            for i in 0..len {
-                                let res = { #stmt };
-                                match res {
-                                    Ok(v) => list.push(v),
-                                    Err(_) => { err_index.get_or_insert(i); }
-                                }
-                            }
+            ByteOrder::LE => {
+                quote! { <#wrapper as ::serdeb::Decode>::decode_le(buf, offset).map(|v| v.0) }
+            }
+        };
+    }
 
-                            if let Some(err_index) = err_index {
-                                Err(())
-                            } else {
-                                Ok(list)
-                            }
-                        };
-                    }
-                }
+    if is_fixed_primitive_type(ty) {
+        return gen_decode_trait(ty, byte_order, DecodeMode::Plain);
+    }
 
-                quote! {}
-            } else {
-                quote! {}
+    if list_elem_type(ty).is_none() && contains_str_type(ty, field_info) {
+        return match &field_info.str_delimiter {
+            Some(str_delimiter) => {
+                let str_delimiter = proc_macro2::Literal::byte_string(str_delimiter.as_slice());
+                quote! { <#ty as ::serdeb::DecodeStr>::decode_str(buf, offset, ::core::option::Option::Some(#str_delimiter)) }
+            }
+            None => {
+                quote! { <#ty as ::serdeb::DecodeStr>::decode_str(buf, offset, ::core::option::Option::None) }
+            }
+        };
+    }
+
+    if let Some(elem) = seq_elem_type(ty) {
+        let len = gen_decode_len(field_info, byte_order);
+        // 长度只属于集合本身, 不传给元素
+        let elem_info = FieldInfo {
+            len_prefix: None,
+            len_from: None,
+            ..field_info.clone()
+        };
+        let stmt = gen_decode_data(&elem, byte_order, &elem_info, mode);
+        let min_size = gen_min_size(&elem, &elem_info);
+        return quote! {
+            match #len {
+                ::core::result::Result::Ok(len) => ::serdeb::__private::decode_seq::<#ty, _>(len, #min_size, || { #stmt }),
+                ::core::result::Result::Err(e) => ::core::result::Result::Err(e),
+            }
+        };
+    }
+
+    // 其他带长度的类型 (例如 `Bytes` 或集合的别名) 由 `DecodeLen` 按个数解码
+    if has_len {
+        let len = gen_decode_len(field_info, byte_order);
+        let decode = match (byte_order, mode) {
+            (ByteOrder::BE, DecodeMode::Source) => quote! {
+                <#ty as ::serdeb::DecodeLen>::decode_len_source_be(__serdeb_source, offset, len)
+            },
+            (ByteOrder::LE, DecodeMode::Source) => quote! {
+                <#ty as ::serdeb::DecodeLen>::decode_len_source_le(__serdeb_source, offset, len)
+            },
+            (ByteOrder::BE, _) => {
+                quote! { <#ty as ::serdeb::DecodeLen>::decode_len_be(buf, offset, len) }
             }
+            (ByteOrder::LE, _) => {
+                quote! { <#ty as ::serdeb::DecodeLen>::decode_len_le(buf, offset, len) }
+            }
+        };
+        return quote! {
+            match #len {
+                ::core::result::Result::Ok(len) => #decode,
+                ::core::result::Result::Err(e) => ::core::result::Result::Err(e),
+            }
+        };
+    }
+
+    gen_decode_trait(ty, byte_order, mode)
+}
+
+/// 集合的元素个数, 来自前面的字段 (len_from) 或紧邻的长度前缀 (len_prefix)
+fn gen_decode_len(field_info: &FieldInfo, byte_order: ByteOrder) -> proc_macro2::TokenStream {
+    if let Some(len_from) = &field_info.len_from {
+        let len_from = Ident::new(len_from, Span::call_site());
+        return quote! { ::core::result::Result::<usize, ::serdeb::error::DecodeError>::Ok(#len_from as usize) };
+    }
+
+    // 调用前已确认有 len_from 或 len_prefix
+    let len_prefix = field_info.len_prefix.as_ref().unwrap();
+
+    match byte_order {
+        ByteOrder::BE => {
+            quote! { <#len_prefix as ::serdeb::Decode>::decode_be(buf, offset).map(::serdeb::LenPrefix::into_len) }
+        }
+        ByteOrder::LE => {
+            quote! { <#len_prefix as ::serdeb::Decode>::decode_le(buf, offset).map(::serdeb::LenPrefix::into_len) }
         }
-        _ => todo!(),
+    }
+}
+
+/// 结构体的 `MIN_SIZE` 和 `READS_TO_END`, 可能不出现的字段不计入最小长度
+fn gen_decode_consts(
+    input: &syn::DeriveInput,
+    struct_info: &StructInfo,
+) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+    let syn::Data::Struct(data_struct) = &input.data else {
+        return (quote! { 0usize }, quote! { false });
+    };
+    let fields: Vec<_> = data_struct.fields.iter().collect();
+    let field_info =
+        |field: &syn::Field| &struct_info.fields[&field.ident.as_ref().unwrap().to_string()];
+
+    let mut min_sizes = Vec::new();
+    let mut reads_to_end = Vec::new();
+    for field in &fields {
+        let field_info = field_info(field);
+        if field_info.skip || field_info.bit_width.is_some() {
+            continue;
+        }
+
+        let ty = field_info
+            .condition
+            .as_ref()
+            .and_then(|_| option_inner_type(&field.ty))
+            .unwrap_or(&field.ty);
+        reads_to_end.push(gen_reads_to_end(ty, field_info));
+
+        let optional = field_info.use_default
+            || field_info.condition.is_some()
+            || field_info.since.is_some()
+            || field_info.until.is_some();
+        if !optional {
+            min_sizes.push(gen_min_size(ty, field_info));
+        }
+    }
+
+    for ([start, end], byte_len) in &struct_info.bitfield_sections {
+        if !fields[*start..*end]
+            .iter()
+            .any(|field| field_info(field).use_default)
+        {
+            min_sizes.push(quote! { #byte_len });
+        }
+    }
+
+    (
+        quote! { 0 #(+ #min_sizes)* },
+        quote! { false #(|| #reads_to_end)* },
+    )
+}
+
+/// 字段编码至少占用的字节数, 用于数据不足时的 `needed` 提示, 无法确定时为 0
+fn gen_min_size(ty: &syn::Type, field_info: &FieldInfo) -> proc_macro2::TokenStream {
+    if field_info.decode_with.is_some() {
+        return quote! { 0usize };
+    }
+
+    if let Some((raw_ty, ..)) = field_info.fixed_point() {
+        return quote! { <#raw_ty as ::serdeb::Decode>::MIN_SIZE };
+    }
+
+    if let Some(wire_ty) = field_info.wire_conversion()
+        && list_elem_type(ty).is_none()
+    {
+        return quote! { <#wire_ty as ::serdeb::Decode>::MIN_SIZE };
+    }
+
+    if let Type::Array(type_array) = ungroup(ty) {
+        let elem_size = gen_min_size(&type_array.elem, field_info);
+        let len = &type_array.len;
+        return quote! { (#elem_size).saturating_mul(#len) };
+    }
+
+    if (is_fixed_primitive_type(ty) || contains_platform_int_type(ty))
+        && let Some(wrapper) = field_info.int_wrapper(ty)
+    {
+        return quote! { <#wrapper as ::serdeb::Decode>::MIN_SIZE };
+    }
+
+    if is_fixed_primitive_type(ty) {
+        return quote! { <#ty as ::serdeb::Decode>::MIN_SIZE };
+    }
+
+    if contains_str_type(ty, field_info)
+        || seq_elem_type(ty).is_some()
+        || field_info.len_from.is_some()
+        || field_info.len_prefix.is_some()
+    {
+        return quote! { 0usize };
+    }
+
+    quote! { <#ty as ::serdeb::Decode>::MIN_SIZE }
+}
+
+/// 字段是否取到输入的结尾, 只有没有分隔符的字符串和嵌套的这类结构体
+fn gen_reads_to_end(ty: &syn::Type, field_info: &FieldInfo) -> proc_macro2::TokenStream {
+    let plain = field_info.decode_with.is_none()
+        && field_info.wire_ty.is_none()
+        && field_info.int_wrapper(ty).is_none()
+        && !matches!(ungroup(ty), Type::Array(_))
+        && !is_fixed_primitive_type(ty)
+        && seq_elem_type(ty).is_none()
+        && field_info.len_from.is_none()
+        && field_info.len_prefix.is_none();
+    if !plain {
+        return quote! { false };
+    }
+
+    if list_elem_type(ty).is_none() && contains_str_type(ty, field_info) {
+        let reads_to_end = field_info.str_delimiter.is_none();
+        return quote! { #reads_to_end };
+    }
+
+    quote! { <#ty as ::serdeb::Decode>::READS_TO_END }
+}
+
+/// 线上值 `v` 转换为字段类型, 失败时错误中带上原始值
+fn gen_try_from_wire(ty: &syn::Type, wire_ty: &syn::Type) -> proc_macro2::TokenStream {
+    let name = ty.to_token_stream().to_string();
+    quote! {
+        <#ty as ::core::convert::TryFrom<#wire_ty>>::try_from(v).map_err(|_| {
+            ::serdeb::error::DecodeError::InvalidValue {
+                name: #name,
+                value: v as i128,
+            }
+        })
     }
 }
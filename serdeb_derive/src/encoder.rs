@@ -8,27 +8,67 @@ pub fn encode_input(input: TokenStream) -> TokenStream {
     match &input.data {
         syn::Data::Struct(_) => {
             let struct_name = &input.ident;
-            let generics = &input.generics;
 
-            let struct_info = Arc::new(StructInfo::parse(&input));
-            let _ = STRUCT_MAP
-                .lock()
-                .unwrap()
-                .insert(struct_name.to_string(), struct_info.clone());
-
-            let stmts_be = encode_struct(&input, ByteOrder::BE, &struct_info);
-            let stmts_le = encode_struct(&input, ByteOrder::LE, &struct_info);
+            let struct_info = match StructInfo::parse(&input) {
+                Ok(struct_info) => struct_info,
+                Err(err) => return err.to_compile_error().into(),
+            };
+
+            let stmts_be = encode_struct(&input, ByteOrder::BE, &struct_info, false);
+            let stmts_le = encode_struct(&input, ByteOrder::LE, &struct_info, false);
+            let stmts_versioned_be = encode_struct(&input, ByteOrder::BE, &struct_info, true);
+            let stmts_versioned_le = encode_struct(&input, ByteOrder::LE, &struct_info, true);
+            let (fixed_sizes, sizes) = encoded_size_struct(&input, &struct_info);
+
+            let size_generics =
+                struct_info.impl_generics(&input, quote! { ::serdeb::EncodedSize }, true);
+            let (size_impl_generics, ty_generics, size_where_clause) =
+                size_generics.split_for_impl();
+            let encode_generics =
+                struct_info.impl_generics(&input, quote! { ::serdeb::Encode }, true);
+            let (impl_generics, _, where_clause) = encode_generics.split_for_impl();
 
             quote! {
-                impl #generics ::serdeb::Encode for #struct_name #generics {
-                    fn encode_be(&self, buf: &mut BytesMut) -> Result<(), ::serdeb::error::EncodeError> {
+                impl #size_impl_generics ::serdeb::EncodedSize for #struct_name #ty_generics #size_where_clause {
+                    const FIXED_SIZE: ::core::option::Option<usize> = {
+                        let size = ::core::option::Option::Some(0);
+                        #(let size = ::serdeb::add_fixed_size(size, #fixed_sizes);)*
+                        size
+                    };
+
+                    /// 自定义编码函数出错时为 0, 需要错误时用 `try_encoded_size`
+                    fn encoded_size(&self) -> usize {
+                        ::serdeb::EncodedSize::try_encoded_size(self).unwrap_or(0)
+                    }
+
+                    fn try_encoded_size(&self) -> ::core::result::Result<usize, ::serdeb::error::EncodeError> {
+                        if let ::core::option::Option::Some(size) = <Self as ::serdeb::EncodedSize>::FIXED_SIZE {
+                            return ::core::result::Result::Ok(size);
+                        }
+
+                        ::core::result::Result::Ok(0 #(+ #sizes)*)
+                    }
+                }
+
+                impl #impl_generics ::serdeb::Encode for #struct_name #ty_generics #where_clause {
+                    fn encode_be<W: ::serdeb::Writer>(&self, buf: &mut W) -> ::core::result::Result<(), ::serdeb::error::EncodeError> {
                         #(#stmts_be)*;
-                        Ok(())
+                        ::core::result::Result::Ok(())
                     }
 
-                    fn encode_le(&self, buf: &mut BytesMut) -> Result<(), ::serdeb::error::EncodeError> {
+                    fn encode_le<W: ::serdeb::Writer>(&self, buf: &mut W) -> ::core::result::Result<(), ::serdeb::error::EncodeError> {
                         #(#stmts_le)*;
-                        Ok(())
+                        ::core::result::Result::Ok(())
+                    }
+
+                    fn encode_versioned_be<W: ::serdeb::Writer>(&self, buf: &mut W, __version: u32) -> ::core::result::Result<(), ::serdeb::error::EncodeError> {
+                        #(#stmts_versioned_be)*;
+                        ::core::result::Result::Ok(())
+                    }
+
+                    fn encode_versioned_le<W: ::serdeb::Writer>(&self, buf: &mut W, __version: u32) -> ::core::result::Result<(), ::serdeb::error::EncodeError> {
+                        #(#stmts_versioned_le)*;
+                        ::core::result::Result::Ok(())
                     }
                 }
             }
@@ -36,32 +76,52 @@ pub fn encode_input(input: TokenStream) -> TokenStream {
         }
         syn::Data::Enum(_) => {
             let enum_name = &input.ident;
-            let enum_info = Arc::new(EnumInfo::parse(&input));
-            let _ = ENUM_MAP
-                .lock()
-                .unwrap()
-                .insert(enum_name.to_string(), enum_info.clone());
+            let enum_info = match EnumInfo::parse(&input) {
+                Ok(enum_info) => enum_info,
+                Err(err) => return err.to_compile_error().into(),
+            };
 
             let stmts_be = encode_enum(&input, ByteOrder::BE, &enum_info);
             let stmts_le = encode_enum(&input, ByteOrder::LE, &enum_info);
 
-            let generics = &input.generics;
+            let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+            let repr_ty = str_to_type(&enum_info.repr_ty);
             quote! {
-                impl #generics ::serdeb::Encode for #enum_name #generics {
-                    fn encode_be(&self, buf: &mut BytesMut) -> Result<(), ::serdeb::error::EncodeError> {
+                impl #impl_generics ::serdeb::EncodedSize for #enum_name #ty_generics #where_clause {
+                    const FIXED_SIZE: ::core::option::Option<usize> = <#repr_ty as ::serdeb::EncodedSize>::FIXED_SIZE;
+
+                    fn encoded_size(&self) -> usize {
+                        ::core::mem::size_of::<#repr_ty>()
+                    }
+                }
+
+                impl #impl_generics ::serdeb::Encode for #enum_name #ty_generics #where_clause {
+                    fn encode_be<W: ::serdeb::Writer>(&self, buf: &mut W) -> ::core::result::Result<(), ::serdeb::error::EncodeError> {
                         #(#stmts_be)*
-                        Ok(())
+                        ::core::result::Result::Ok(())
                     }
 
-                    fn encode_le(&self, buf: &mut BytesMut) -> Result<(), ::serdeb::error::EncodeError> {
+                    fn encode_le<W: ::serdeb::Writer>(&self, buf: &mut W) -> ::core::result::Result<(), ::serdeb::error::EncodeError> {
                         #(#stmts_le)*
-                        Ok(())
+                        ::core::result::Result::Ok(())
+                    }
+                }
+
+                impl #impl_generics ::serdeb::EncodeBits for #enum_name #ty_generics #where_clause {
+                    type Raw = #repr_ty;
+
+                    fn to_bits(&self) -> #repr_ty {
+                        *self as #repr_ty
                     }
                 }
             }
             .into()
         }
-        syn::Data::Union(_) => todo!(),
+        syn::Data::Union(data_union) => {
+            syn::Error::new_spanned(data_union.union_token, "serdeb does not support unions")
+                .to_compile_error()
+                .into()
+        }
     }
 }
 
@@ -69,6 +129,7 @@ fn encode_struct(
     input: &syn::DeriveInput,
     byte_order: ByteOrder,
     struct_info: &StructInfo,
+    versioned: bool,
 ) -> Vec<proc_macro2::TokenStream> {
     let mut encode_stmts: Vec<proc_macro2::TokenStream> = Vec::new();
 
@@ -76,6 +137,10 @@ fn encode_struct(
         return encode_stmts;
     };
 
+    if struct_info.has_computed {
+        encode_stmts.push(quote! { let __serdeb_start = ::serdeb::__private::position(buf)?; });
+    }
+
     if let syn::Fields::Named(fields) = &data_struct.fields {
         let mut field_index = 0;
         let mut bitfield_section_index = 0;
@@ -92,6 +157,18 @@ fn encode_struct(
                 let section_start = section[0];
                 let section_end = section[1];
 
+                let section_names: Vec<Ident> = (section_start..section_end)
+                    .map(|i| fields.named[i].ident.clone().unwrap())
+                    .collect();
+
+                if struct_info.has_computed {
+                    for name in &section_names {
+                        let (start, _) = field_position_idents(name);
+                        encode_stmts
+                            .push(quote! { let #start = ::serdeb::__private::position(buf)?; });
+                    }
+                }
+
                 encode_stmts.push(quote! {
                     let mut byte_list = [0u8; #byte_len];
                 });
@@ -103,29 +180,39 @@ fn encode_struct(
                     let name = field.ident.clone().unwrap();
                     let name_str = name.to_string();
                     let ty = &field.ty;
-                    let ty_str = ty.to_token_stream().to_string();
                     let field_info = &struct_info.fields[&name_str];
 
-                    if ty_str == "bool" {
-                        encode_stmts.push(quote! { let v = self.#name as u8; })
-                    } else if is_enum_type(&ty_str) {
-                        let enum_info = get_enum_info(&ty_str).unwrap();
-                        let fixed_primitive_ty = str_to_type(&enum_info.repr_ty);
-                        encode_stmts.push(quote! { let v = self.#name as #fixed_primitive_ty; })
+                    if field_info.skip {
+                        continue;
+                    }
+
+                    if let Some(wire_ty) = field_info.wire_conversion() {
+                        encode_stmts.push(gen_try_into_wire(
+                            quote! { &self.#name },
+                            &wire_ty,
+                            &name_str,
+                        ))
+                    } else if let Some((raw_ty, scale, offset)) = field_info.fixed_point() {
+                        encode_stmts.push(quote! {
+                            let v: #raw_ty = ::serdeb::__private::to_fixed(#name_str, self.#name as f64, #scale, #offset)?;
+                        })
                     } else {
-                        encode_stmts.push(quote! { let v = self.#name; })
+                        encode_stmts.push(quote! {
+                            let v = <#ty as ::serdeb::EncodeBits>::to_bits(&self.#name);
+                        })
                     }
 
                     let bit_width = field_info.bit_width.unwrap();
                     let start_bit = bit_offset;
 
-                    let max_value = 2u64.pow(bit_width as u32) - 1;
+                    let max_value = u128::MAX >> (128 - bit_width);
 
-                    let int = syn::LitInt::new(&max_value.to_string(), Span::call_site());
+                    let int = syn::LitInt::new(&format!("{max_value}u128"), Span::call_site());
 
+                    // 负数按 u128 比较时一定越界
                     encode_stmts.push(quote! {
-                        if v > #int {
-                            return Err(serdeb::error::EncodeError::BitWidthLimit { field: #name_str, value: v.to_string() });
+                        if v as u128 > #int {
+                            return ::core::result::Result::Err(::serdeb::error::EncodeError::BitWidthLimit { field: #name_str, value: v as u128 });
                         }
                     });
 
@@ -155,7 +242,21 @@ fn encode_struct(
                     bit_offset += bit_width;
                 }
 
-                encode_stmts.push(quote! { buf.extend_from_slice(&byte_list);});
+                encode_stmts.push(quote! { ::serdeb::Writer::write_slice(buf, &byte_list)?;});
+
+                if struct_info.has_computed {
+                    for name in &section_names {
+                        let (_, end) = field_position_idents(name);
+                        encode_stmts
+                            .push(quote! { let #end = ::serdeb::__private::position(buf)?; });
+                    }
+                }
+
+                for (field, field_info) in
+                    struct_info.computed_fields_after(fields, section_start..section_end)
+                {
+                    encode_stmts.push(gen_computed_fill(field, field_info, byte_order));
+                }
 
                 field_index = section_end;
                 bitfield_section_index += 1;
@@ -163,26 +264,155 @@ fn encode_struct(
                 let field = &fields.named[field_index];
                 let name = field.ident.clone().unwrap();
                 let name_str = name.to_string();
-                let ty_str = field.ty.to_token_stream().to_string();
                 let field_info = &struct_info.fields[&name_str];
 
-                let stmt = if is_str_type(&ty_str) {
-                    match &field_info.str_delimiter {
-                        Some(str_delimiter) => {
-                            let str_delimiter =
-                                proc_macro2::Literal::byte_string(str_delimiter.as_slice());
-                            quote! { self.#name.encode_str(buf, Some(#str_delimiter)); }
-                        }
-                        None => quote! { self.#name.encode_str(buf, None); },
+                if field_info.skip {
+                    field_index += 1;
+                    continue;
+                }
+
+                // 条件字段为 Option<T> 时, 只编码 Some 中的值
+                let option_inner_ty = field_info
+                    .condition
+                    .as_ref()
+                    .and_then(|_| option_inner_type(&field.ty));
+                let (value, value_ty) = match option_inner_ty {
+                    Some(inner_ty) => (quote! { v }, inner_ty),
+                    None => (quote! { self.#name }, &field.ty),
+                };
+
+                let stmt = if field_info.computed.is_some() {
+                    // 计算字段忽略结构体中的值, 先占位, 范围内的字段写完后回填
+                    let ty = &field.ty;
+                    let placeholder = placeholder_ident(&name);
+                    quote! {
+                        let #placeholder = ::serdeb::placeholder::ReserveField::reserve_field::<#ty>(buf)?;
+                    }
+                } else if let Some(encode_with) = &field_info.encode_with {
+                    let value_ref = match option_inner_ty {
+                        Some(_) => quote! { v },
+                        None => quote! { &self.#name },
+                    };
+                    let endian = endian_tokens(byte_order);
+                    let context = field_info.context_arg(|name| quote! { self.#name });
+                    quote! { #encode_with(#value_ref, buf, #endian #context)?; }
+                } else if let Some(wire_ty) = field_info.wire_conversion() {
+                    let value_ref = match option_inner_ty {
+                        Some(_) => quote! { v },
+                        None => quote! { &self.#name },
+                    };
+                    gen_encode_as(value_ref, value_ty, &wire_ty, &name_str, byte_order)
+                } else if let Some((raw_ty, scale, offset)) = field_info.fixed_point() {
+                    let scalar = match option_inner_ty {
+                        Some(_) => quote! { *v },
+                        None => value.clone(),
+                    };
+                    let encode_raw = match byte_order {
+                        ByteOrder::BE => quote! { ::serdeb::Encode::encode_be(&v, buf)?; },
+                        ByteOrder::LE => quote! { ::serdeb::Encode::encode_le(&v, buf)?; },
+                    };
+                    quote! {
+                        let v: #raw_ty = ::serdeb::__private::to_fixed(#name_str, #scalar as f64, #scale, #offset)?;
+                        #encode_raw
                     }
+                } else if field_info.int_wrapper(value_ty).is_some() {
+                    let scalar = match option_inner_ty {
+                        Some(_) => quote! { *v },
+                        None => value.clone(),
+                    };
+                    gen_encode_wrapped(scalar, value_ty, field_info, byte_order)
+                } else if contains_str_type(value_ty, field_info) {
+                    gen_encode_str(value.clone(), value_ty, field_info)
                 } else {
-                    match byte_order {
-                        ByteOrder::BE => quote! { self.#name.encode_be(buf)?; },
-                        ByteOrder::LE => quote! { self.#name.encode_le(buf)?; },
+                    match (byte_order, versioned) {
+                        (ByteOrder::BE, false) => {
+                            quote! { <#value_ty as ::serdeb::Encode>::encode_be(&#value, buf)?; }
+                        }
+                        (ByteOrder::LE, false) => {
+                            quote! { <#value_ty as ::serdeb::Encode>::encode_le(&#value, buf)?; }
+                        }
+                        (ByteOrder::BE, true) => quote! {
+                            <#value_ty as ::serdeb::Encode>::encode_versioned_be(&#value, buf, __version)?;
+                        },
+                        (ByteOrder::LE, true) => quote! {
+                            <#value_ty as ::serdeb::Encode>::encode_versioned_le(&#value, buf, __version)?;
+                        },
+                    }
+                };
+
+                let stmt = match &field_info.len_prefix {
+                    Some(len_prefix) => {
+                        let encode_len = match byte_order {
+                            ByteOrder::BE => quote! { ::serdeb::Encode::encode_be(&len, buf)?; },
+                            ByteOrder::LE => quote! { ::serdeb::Encode::encode_le(&len, buf)?; },
+                        };
+                        quote! {
+                            let len = <#len_prefix as ::serdeb::LenPrefix>::from_len(#value.len())
+                                .ok_or(::serdeb::error::EncodeError::LengthOverflow { field: #name_str, len: #value.len() })?;
+                            #encode_len
+                            #stmt
+                        }
                     }
+                    None => stmt,
                 };
 
-                encode_stmts.push(stmt);
+                // 条件与 Some/None 不一致时编出的数据无法解码
+                let mismatch = quote! {
+                    return ::core::result::Result::Err(
+                        ::serdeb::error::EncodeError::ConditionMismatch { field: #name_str },
+                    )
+                };
+                let stmt = match option_inner_ty {
+                    Some(_) => quote! {
+                        match &self.#name {
+                            ::core::option::Option::Some(v) => { #stmt }
+                            ::core::option::Option::None => #mismatch,
+                        }
+                    },
+                    None => stmt,
+                };
+
+                let mut guards = Vec::new();
+                if let Some(condition) = &field_info.condition {
+                    let condition = self_condition(condition, &struct_info.fields);
+                    if option_inner_ty.is_some() {
+                        encode_stmts.push(quote! {
+                            if !(#condition) && self.#name.is_some() {
+                                #mismatch;
+                            }
+                        });
+                    }
+                    guards.push(quote! { #condition });
+                }
+                if versioned && let Some(version_guard) = field_info.version_guard() {
+                    guards.push(version_guard);
+                }
+
+                let stmt = match join_guards(guards) {
+                    Some(guard) => quote! {
+                        if #guard {
+                            #stmt
+                        }
+                    },
+                    None => stmt,
+                };
+
+                if struct_info.has_computed {
+                    let (start, end) = field_position_idents(&name);
+                    encode_stmts.push(quote! {
+                        let #start = ::serdeb::__private::position(buf)?;
+                        #stmt
+                        let #end = ::serdeb::__private::position(buf)?;
+                    });
+                } else {
+                    encode_stmts.push(stmt);
+                }
+
+                for (field, field_info) in
+                    struct_info.computed_fields_after(fields, field_index..field_index + 1)
+                {
+                    encode_stmts.push(gen_computed_fill(field, field_info, byte_order));
+                }
 
                 field_index += 1;
             }
@@ -192,6 +422,333 @@ fn encode_struct(
     encode_stmts
 }
 
+/// 每个字段/位域段的定长表达式和实际长度表达式
+fn encoded_size_struct(
+    input: &syn::DeriveInput,
+    struct_info: &StructInfo,
+) -> (Vec<proc_macro2::TokenStream>, Vec<proc_macro2::TokenStream>) {
+    let mut fixed_sizes = Vec::new();
+    let mut sizes = Vec::new();
+
+    let syn::Data::Struct(data_struct) = &input.data else {
+        return (fixed_sizes, sizes);
+    };
+
+    let syn::Fields::Named(fields) = &data_struct.fields else {
+        return (fixed_sizes, sizes);
+    };
+
+    for (_, byte_len) in &struct_info.bitfield_sections {
+        fixed_sizes.push(quote! { ::core::option::Option::Some(#byte_len) });
+        sizes.push(quote! { #byte_len });
+    }
+
+    for field in fields.named.iter() {
+        let name = field.ident.clone().unwrap();
+        let field_info = &struct_info.fields[&name.to_string()];
+        if field_info.bit_width.is_some() || field_info.skip {
+            continue;
+        }
+
+        let option_inner_ty = field_info
+            .condition
+            .as_ref()
+            .and_then(|_| option_inner_type(&field.ty));
+        let (value, value_ref, ty) = match option_inner_ty {
+            Some(inner_ty) => (quote! { v }, quote! { v }, inner_ty),
+            None => (quote! { self.#name }, quote! { &self.#name }, &field.ty),
+        };
+
+        let size = if let Some(encode_with) = &field_info.encode_with {
+            // 自定义编码的长度只能实际编码一遍得到
+            let context = field_info.context_arg(|name| quote! { self.#name });
+            fixed_sizes.push(quote! { ::core::option::Option::None });
+            quote! {
+                ::serdeb::__private::encoded_size_with(|buf| {
+                    #encode_with(#value_ref, buf, ::serdeb::Endian::Big #context)
+                })?
+            }
+        } else if let Some(wire_ty) = field_info.wire_conversion() {
+            if field_info.condition.is_some() {
+                fixed_sizes.push(quote! { ::core::option::Option::None });
+            } else {
+                fixed_sizes.push(gen_as_fixed_size(ty, &wire_ty));
+            }
+            gen_as_size(value_ref.clone(), ty, &wire_ty)
+        } else if let Some((raw_ty, _, _)) = field_info.fixed_point() {
+            if field_info.condition.is_some() {
+                fixed_sizes.push(quote! { ::core::option::Option::None });
+            } else {
+                fixed_sizes.push(quote! { <#raw_ty as ::serdeb::EncodedSize>::FIXED_SIZE });
+            }
+            quote! { ::core::mem::size_of::<#raw_ty>() }
+        } else if field_info.int_wrapper(ty).is_some() {
+            if field_info.condition.is_some() {
+                fixed_sizes.push(quote! { ::core::option::Option::None });
+            } else {
+                fixed_sizes.push(gen_wrapped_fixed_size(ty, field_info));
+            }
+            gen_wrapped_size(quote! { *#value_ref }, ty, field_info)
+        } else if contains_str_type(ty, field_info) {
+            fixed_sizes.push(quote! { ::core::option::Option::None });
+            gen_str_size(value.clone(), ty, field_info)
+        } else {
+            if field_info.condition.is_some() {
+                fixed_sizes.push(quote! { ::core::option::Option::None });
+            } else {
+                fixed_sizes.push(quote! { <#ty as ::serdeb::EncodedSize>::FIXED_SIZE });
+            }
+            quote! { ::serdeb::EncodedSize::try_encoded_size(#value_ref)? }
+        };
+
+        let size = match &field_info.len_prefix {
+            Some(len_prefix) => {
+                quote! {
+                    #size + <#len_prefix as ::serdeb::LenPrefix>::from_len(#value.len())
+                        .map_or(0, |len| ::serdeb::EncodedSize::encoded_size(&len))
+                }
+            }
+            None => size,
+        };
+
+        let size = match option_inner_ty {
+            Some(_) => quote! {
+                match &self.#name {
+                    ::core::option::Option::Some(v) => #size,
+                    ::core::option::Option::None => 0,
+                }
+            },
+            None => size,
+        };
+
+        let size = match &field_info.condition {
+            Some(condition) => {
+                let condition = self_condition(condition, &struct_info.fields);
+                quote! { if #condition { #size } else { 0 } }
+            }
+            None => size,
+        };
+
+        sizes.push(quote! { (#size) });
+    }
+
+    (fixed_sizes, sizes)
+}
+
+/// 字符串逐个带分隔符编码, 数组/`Vec` 逐个元素展开
+fn gen_encode_str(
+    value: proc_macro2::TokenStream,
+    ty: &syn::Type,
+    field_info: &FieldInfo,
+) -> proc_macro2::TokenStream {
+    match list_elem_type(ty) {
+        Some(elem) => {
+            let stmt = gen_encode_str(quote! { v }, &elem, field_info);
+            quote! {
+                for v in #value.iter() {
+                    #stmt
+                }
+            }
+        }
+        None => match &field_info.str_delimiter {
+            Some(str_delimiter) => {
+                let str_delimiter = proc_macro2::Literal::byte_string(str_delimiter.as_slice());
+                quote! { <#ty as ::serdeb::EncodeStr>::encode_str(&#value, buf, ::core::option::Option::Some(#str_delimiter))?; }
+            }
+            None => {
+                quote! { <#ty as ::serdeb::EncodeStr>::encode_str(&#value, buf, ::core::option::Option::None)?; }
+            }
+        },
+    }
+}
+
+fn gen_str_size(
+    value: proc_macro2::TokenStream,
+    ty: &syn::Type,
+    field_info: &FieldInfo,
+) -> proc_macro2::TokenStream {
+    match list_elem_type(ty) {
+        Some(elem) => {
+            let size = gen_str_size(quote! { v }, &elem, field_info);
+            quote! { #value.iter().map(|v| #size).sum::<usize>() }
+        }
+        None => {
+            let delimiter_len = field_info.str_delimiter.as_ref().map_or(0, Vec::len);
+            quote! { #value.len() + #delimiter_len }
+        }
+    }
+}
+
+/// 转换成线上类型后编码, 数组/序列逐个元素转换
+fn gen_encode_as(
+    value_ref: proc_macro2::TokenStream,
+    ty: &syn::Type,
+    wire_ty: &syn::Type,
+    name_str: &str,
+    byte_order: ByteOrder,
+) -> proc_macro2::TokenStream {
+    match list_elem_type(ty) {
+        Some(elem) => {
+            let stmt = gen_encode_as(quote! { v }, &elem, wire_ty, name_str, byte_order);
+            quote! {
+                for v in (#value_ref).iter() {
+                    #stmt
+                }
+            }
+        }
+        None => {
+            let encode = match byte_order {
+                ByteOrder::BE => quote! { ::serdeb::Encode::encode_be(&v, buf)?; },
+                ByteOrder::LE => quote! { ::serdeb::Encode::encode_le(&v, buf)?; },
+            };
+            let try_into = gen_try_into_wire(value_ref, wire_ty, name_str);
+            quote! {
+                #try_into
+                #encode
+            }
+        }
+    }
+}
+
+/// 字段值转换为线上类型, 超出范围时返回错误
+fn gen_try_into_wire(
+    value_ref: proc_macro2::TokenStream,
+    wire_ty: &syn::Type,
+    name_str: &str,
+) -> proc_macro2::TokenStream {
+    quote! {
+        let v = <#wire_ty as ::core::convert::TryFrom<_>>::try_from(::core::clone::Clone::clone(#value_ref))
+            .map_err(|_| ::serdeb::error::EncodeError::InvalidValue { field: #name_str })?;
+    }
+}
+
+fn gen_as_size(
+    value_ref: proc_macro2::TokenStream,
+    ty: &syn::Type,
+    wire_ty: &syn::Type,
+) -> proc_macro2::TokenStream {
+    match list_elem_type(ty) {
+        Some(elem) if list_elem_type(&elem).is_some() => {
+            let size = gen_as_size(quote! { v }, &elem, wire_ty);
+            quote! { (#value_ref).iter().map(|v| #size).sum::<usize>() }
+        }
+        Some(_) => quote! { (#value_ref).len() * ::core::mem::size_of::<#wire_ty>() },
+        None => quote! { ::core::mem::size_of::<#wire_ty>() },
+    }
+}
+
+fn gen_as_fixed_size(ty: &syn::Type, wire_ty: &syn::Type) -> proc_macro2::TokenStream {
+    match ty {
+        Type::Array(type_array) => {
+            let elem_size = gen_as_fixed_size(&type_array.elem, wire_ty);
+            let len = &type_array.len;
+            quote! { ::serdeb::mul_fixed_size(#elem_size, #len) }
+        }
+        _ if list_elem_type(ty).is_some() => quote! { ::core::option::Option::None },
+        _ => quote! { <#wire_ty as ::serdeb::EncodedSize>::FIXED_SIZE },
+    }
+}
+
+/// varint/bytes 字段用 `VarInt`/`ZigZag`/`NarrowInt` 包装后编码, 数组/序列逐个元素包装
+fn gen_encode_wrapped(
+    value: proc_macro2::TokenStream,
+    ty: &syn::Type,
+    field_info: &FieldInfo,
+    byte_order: ByteOrder,
+) -> proc_macro2::TokenStream {
+    match list_elem_type(ty) {
+        Some(elem) => {
+            let stmt = gen_encode_wrapped(quote! { *v }, &elem, field_info, byte_order);
+            quote! {
+                for v in #value.iter() {
+                    #stmt
+                }
+            }
+        }
+        None => {
+            let wrapper = field_info.int_wrapper(ty).unwrap();
+            match byte_order {
+                ByteOrder::BE => quote! { ::serdeb::Encode::encode_be(&#wrapper(#value), buf)?; },
+                ByteOrder::LE => quote! { ::serdeb::Encode::encode_le(&#wrapper(#value), buf)?; },
+            }
+        }
+    }
+}
+
+fn gen_wrapped_size(
+    value: proc_macro2::TokenStream,
+    ty: &syn::Type,
+    field_info: &FieldInfo,
+) -> proc_macro2::TokenStream {
+    match list_elem_type(ty) {
+        Some(elem) => {
+            let size = gen_wrapped_size(quote! { *v }, &elem, field_info);
+            quote! { #value.iter().map(|v| #size).sum::<usize>() }
+        }
+        None => {
+            let wrapper = field_info.int_wrapper(ty).unwrap();
+            quote! { ::serdeb::EncodedSize::encoded_size(&#wrapper(#value)) }
+        }
+    }
+}
+
+fn gen_wrapped_fixed_size(ty: &syn::Type, field_info: &FieldInfo) -> proc_macro2::TokenStream {
+    match ungroup(ty) {
+        syn::Type::Array(type_array) => {
+            let elem_size = gen_wrapped_fixed_size(&type_array.elem, field_info);
+            let len = &type_array.len;
+            quote! { ::serdeb::mul_fixed_size(#elem_size, #len) }
+        }
+        _ if list_elem_type(ty).is_some() => quote! { ::core::option::Option::None },
+        _ => {
+            let wrapper = field_info.int_wrapper(ty).unwrap();
+            quote! { <#wrapper as ::serdeb::EncodedSize>::FIXED_SIZE }
+        }
+    }
+}
+
+fn gen_computed_fill(
+    field: &syn::Field,
+    field_info: &FieldInfo,
+    byte_order: ByteOrder,
+) -> proc_macro2::TokenStream {
+    let name = field.ident.clone().unwrap();
+    let name_str = name.to_string();
+    let ty = &field.ty;
+    let placeholder = placeholder_ident(&name);
+    let [start, end] = field_info
+        .range
+        .as_ref()
+        .unwrap()
+        .clone()
+        .map(|v| Ident::new(&v, Span::call_site()));
+
+    let endian = endian_tokens(byte_order);
+
+    let value_stmt = match field_info.computed.as_ref().unwrap() {
+        ComputedField::Checksum(checksum) => {
+            quote! {
+                let v = <#checksum as ::serdeb::checksum::Checksum>::checksum(&::serdeb::Backpatch::written(__serdeb_patch)[#start..#end]) as #ty;
+            }
+        }
+        ComputedField::Length => quote! {
+            let len = #end - #start;
+            let v = <#ty>::try_from(len).map_err(|_| ::serdeb::error::EncodeError::LengthOverflow {
+                field: #name_str,
+                len,
+            })?;
+        },
+    };
+
+    // 只计数的 writer 没有已写入的数据可读, 其他不支持回填的 writer 在记录位置时已经报错
+    quote! {
+        if let ::core::option::Option::Some(__serdeb_patch) = ::serdeb::Writer::as_backpatch(buf) {
+            #value_stmt
+            #placeholder.fill(__serdeb_patch, v, #endian)?;
+        }
+    }
+}
+
 fn encode_enum(
     _input: &syn::DeriveInput,
     byte_order: ByteOrder,
@@ -202,8 +759,8 @@ fn encode_enum(
     let repr_ty = str_to_type(&enum_info.repr_ty);
 
     let stmt = match byte_order {
-        ByteOrder::BE => quote! { v.encode_be(buf)?; },
-        ByteOrder::LE => quote! { v.encode_le(buf)?; },
+        ByteOrder::BE => quote! { ::serdeb::Encode::encode_be(&v, buf)?; },
+        ByteOrder::LE => quote! { ::serdeb::Encode::encode_le(&v, buf)?; },
     };
 
     encode_stmts.push(quote! {
@@ -6,53 +6,164 @@ mod encoder;
 use std::{
     collections::{BTreeMap, HashMap},
     str::FromStr,
-    sync::{Arc, LazyLock, Mutex},
 };
 
 use proc_macro::TokenStream;
 use proc_macro2::Span;
-use quote::ToTokens;
+use quote::{ToTokens, quote};
 use syn::{
     Ident, Meta, Path, PathSegment, Result, Token, Type, TypePath,
     parse::{Parse, ParseStream},
     punctuated::Punctuated,
+    visit_mut::{self, VisitMut},
 };
 
 struct MetaListParser(Punctuated<Meta, Token![,]>);
 
 impl Parse for MetaListParser {
     fn parse(input: ParseStream) -> Result<Self> {
-        Ok(MetaListParser(Punctuated::parse_terminated(input)?))
+        Ok(MetaListParser(Punctuated::parse_terminated_with(
+            input, parse_meta,
+        )?))
     }
 }
 
+/// `Meta` 默认不接受关键字作为路径, 这里额外支持 `if = "..."`, `as = u16`,
+/// 以及值为类型的 `len_prefix = VarInt<u32>`
+fn parse_meta(input: ParseStream) -> Result<Meta> {
+    if input.peek(Token![as]) {
+        let as_token: Token![as] = input.parse()?;
+        let eq_token = input.parse()?;
+        let ty: Type = input.parse()?;
+        return Ok(Meta::NameValue(syn::MetaNameValue {
+            path: Path::from(Ident::new("as", as_token.span)),
+            eq_token,
+            value: syn::Expr::Verbatim(ty.to_token_stream()),
+        }));
+    }
+
+    if input.peek(Ident) && input.fork().parse::<Ident>()? == "len_prefix" {
+        let path: Path = input.parse()?;
+        let eq_token = input.parse()?;
+        let ty: Type = input.parse()?;
+        return Ok(Meta::NameValue(syn::MetaNameValue {
+            path,
+            eq_token,
+            value: syn::Expr::Verbatim(ty.to_token_stream()),
+        }));
+    }
+
+    if input.peek(Token![if]) {
+        let if_token: Token![if] = input.parse()?;
+        return Ok(Meta::NameValue(syn::MetaNameValue {
+            path: Path::from(Ident::new("if", if_token.span)),
+            eq_token: input.parse()?,
+            value: input.parse()?,
+        }));
+    }
+
+    input.parse()
+}
+
 fn parse_int<T: std::str::FromStr>(expr: &syn::Expr) -> Option<T>
 where
     <T as FromStr>::Err: std::fmt::Display,
 {
-    if let syn::Expr::Lit(expr_lit) = expr {
-        if let syn::Lit::Int(lit) = &expr_lit.lit {
-            return lit.base10_parse::<T>().ok();
-        }
+    if let syn::Expr::Lit(expr_lit) = expr
+        && let syn::Lit::Int(lit) = &expr_lit.lit
+    {
+        return lit.base10_parse::<T>().ok();
     }
     None
 }
 
+/// 收集派生过程中的错误, 一次报告所有问题
+#[derive(Default)]
+struct Errors(Vec<syn::Error>);
+
+impl Errors {
+    fn push(&mut self, tokens: impl ToTokens, message: impl std::fmt::Display) {
+        self.0.push(syn::Error::new_spanned(tokens, message));
+    }
+
+    fn push_error(&mut self, error: syn::Error) {
+        self.0.push(error);
+    }
+
+    fn finish(self) -> Result<()> {
+        let mut errors = self.0.into_iter();
+        let Some(mut error) = errors.next() else {
+            return Ok(());
+        };
+        for e in errors {
+            error.combine(e);
+        }
+        Err(error)
+    }
+}
+
+/// `len_from = len` 这类只接受单个标识符的属性值
+fn expr_ident(expr: &syn::Expr) -> Option<&Ident> {
+    match expr {
+        syn::Expr::Path(expr_path) => expr_path.path.get_ident(),
+        _ => None,
+    }
+}
+
+/// 类型中是否出现了指定的标识符, 用于判断字段是否用到了类型参数
+fn type_uses_ident(ty: &Type, ident: &Ident) -> bool {
+    fn visit(tokens: proc_macro2::TokenStream, ident: &Ident) -> bool {
+        tokens.into_iter().any(|tt| match tt {
+            proc_macro2::TokenTree::Ident(i) => &i == ident,
+            proc_macro2::TokenTree::Group(group) => visit(group.stream(), ident),
+            _ => false,
+        })
+    }
+
+    visit(ty.to_token_stream(), ident)
+}
+
+/// 支持 `1`, `0.01`, `-40.0` 这样的数字字面量
+fn parse_float(expr: &syn::Expr) -> Option<f64> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Float(lit),
+            ..
+        }) => lit.base10_parse().ok(),
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Int(lit),
+            ..
+        }) => lit.base10_parse().ok(),
+        syn::Expr::Unary(syn::ExprUnary {
+            op: syn::UnOp::Neg(_),
+            expr,
+            ..
+        }) => parse_float(expr).map(|v: f64| -v),
+        _ => None,
+    }
+}
+
 fn is_fixed_primitive_type_str(ty_str: &str) -> bool {
     matches!(
         ty_str,
-        "bool" | "u8" | "i8" | "u16" | "i16" | "u32" | "i32" | "u64" | "i64" | "f32" | "f64"
+        "bool"
+            | "u8"
+            | "i8"
+            | "u16"
+            | "i16"
+            | "u32"
+            | "i32"
+            | "u64"
+            | "i64"
+            | "u128"
+            | "i128"
+            | "f32"
+            | "f64"
     )
 }
 
 fn is_fixed_primitive_type(ty: &Type) -> bool {
-    match ty {
-        Type::Path(tp) => {
-            let ident = &tp.path.segments.last().unwrap().ident.to_string();
-            is_fixed_primitive_type_str(ident.as_str())
-        }
-        _ => false,
-    }
+    last_ident(ty).is_some_and(|ident| is_fixed_primitive_type_str(&ident))
 }
 
 fn is_str_type(ty: &str) -> bool {
@@ -60,19 +171,71 @@ fn is_str_type(ty: &str) -> bool {
 }
 
 fn is_dynamic_list_type(ty: &str) -> bool {
-    matches!(ty, "Vec")
+    matches!(
+        ty,
+        "Vec" | "VecDeque" | "LinkedList" | "BTreeSet" | "HashSet"
+    )
+}
+
+fn is_map_type(ty: &str) -> bool {
+    matches!(ty, "BTreeMap" | "HashMap")
+}
+
+/// 可以包裹 `str` 或 `[T]` 的指针类型
+fn is_pointer_type(ty: &str) -> bool {
+    matches!(ty, "Box" | "Rc" | "Arc" | "Cow")
+}
+
+/// `usize`/`isize` 或元素为它们的集合, 宽度与平台相关, 不能直接编码
+fn contains_platform_int_type(ty: &Type) -> bool {
+    let ty = option_inner_type(ty).unwrap_or(ty);
+    match list_elem_type(ty) {
+        Some(elem) => contains_platform_int_type(&elem),
+        None => last_ident(ty).is_some_and(|ident| ident == "usize" || ident == "isize"),
+    }
+}
+
+/// 去掉 `Option` 和数组/序列后的元素类型名
+fn leaf_ident(ty: &Type) -> Option<String> {
+    let ty = option_inner_type(ty).unwrap_or(ty);
+    match list_elem_type(ty) {
+        Some(elem) => leaf_ident(&elem),
+        None => last_ident(ty),
+    }
 }
 
-fn is_enum_type(enum_name: &str) -> bool {
-    ENUM_MAP.lock().unwrap().contains_key(enum_name)
+/// 字段(或其元素)为内置整数时的字节数
+fn leaf_int_size(ty: &Type) -> Option<usize> {
+    match leaf_ident(ty)?.as_str() {
+        "u8" | "i8" => Some(1),
+        "u16" | "i16" => Some(2),
+        "u32" | "i32" => Some(4),
+        "u64" | "i64" => Some(8),
+        "u128" | "i128" => Some(16),
+        _ => None,
+    }
 }
 
-fn get_enum_info(enum_name: &str) -> Option<Arc<EnumInfo>> {
-    ENUM_MAP.lock().unwrap().get(enum_name).cloned()
+/// 位域字段 `EncodeBits::Raw` 的位数, 自定义实现的类型返回 `None`
+fn bits_raw_width(ty: &Type) -> Option<usize> {
+    match last_ident(ty)?.as_str() {
+        "bool" | "u8" | "i8" => Some(8),
+        "u16" | "i16" => Some(16),
+        "char" | "u32" | "i32" => Some(32),
+        "u64" | "i64" => Some(64),
+        "u128" | "i128" => Some(128),
+        "NonZero" | "Wrapping" => bits_raw_width(generic_type_args(ty).first()?),
+        _ => None,
+    }
 }
 
-fn is_struct_type(struct_name: &str) -> bool {
-    STRUCT_MAP.lock().unwrap().contains_key(struct_name)
+/// 宏展开得到的类型会包在 `Type::Group` 中, 括号类型同理
+fn ungroup(ty: &Type) -> &Type {
+    match ty {
+        Type::Group(group) => ungroup(&group.elem),
+        Type::Paren(paren) => ungroup(&paren.elem),
+        _ => ty,
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -81,6 +244,13 @@ enum ByteOrder {
     LE,
 }
 
+fn endian_tokens(byte_order: ByteOrder) -> proc_macro2::TokenStream {
+    match byte_order {
+        ByteOrder::BE => quote! { ::serdeb::Endian::Big },
+        ByteOrder::LE => quote! { ::serdeb::Endian::Little },
+    }
+}
+
 #[proc_macro_derive(Encoder, attributes(byte_order, serdeb))]
 pub fn encoder_derive(input: TokenStream) -> TokenStream {
     encoder::encode_input(input)
@@ -104,22 +274,166 @@ fn str_to_type(str: &str) -> Type {
     })
 }
 
-/// 将 Ident 转成 syn::Type
-// fn ident_to_type(ident: Ident) -> Type {
-//     Type::Path(TypePath {
-//         qself: None,
-//         path: Path {
-//             leading_colon: None,
-//             segments: vec![PathSegment::from(ident.clone())].into_iter().collect(),
-//         },
-//     })
-// }
-
-static ENUM_MAP: LazyLock<Mutex<HashMap<String, Arc<EnumInfo>>>> =
-    LazyLock::new(|| Mutex::new(HashMap::new()));
-
-static STRUCT_MAP: LazyLock<Mutex<HashMap<String, Arc<StructInfo>>>> =
-    LazyLock::new(|| Mutex::new(HashMap::new()));
+/// 字段在缓冲区中的起止位置变量, 用于计算校验和
+fn field_position_idents(name: &Ident) -> (Ident, Ident) {
+    (
+        quote::format_ident!("__{}_start", name),
+        quote::format_ident!("__{}_end", name),
+    )
+}
+
+/// 预留位置的变量名
+fn placeholder_ident(name: &Ident) -> Ident {
+    quote::format_ident!("__{}_placeholder", name)
+}
+
+/// 合并多个判断条件
+fn join_guards(guards: Vec<proc_macro2::TokenStream>) -> Option<proc_macro2::TokenStream> {
+    if guards.len() <= 1 {
+        return guards.into_iter().next();
+    }
+
+    guards
+        .into_iter()
+        .map(|guard| quote! { (#guard) })
+        .reduce(|acc, guard| quote! { #acc && #guard })
+}
+
+/// 取出 `Option<T>` 中的 T
+fn option_inner_type(ty: &Type) -> Option<&Type> {
+    let Type::Path(type_path) = ungroup(ty) else {
+        return None;
+    };
+
+    let seg = type_path.path.segments.last()?;
+    if seg.ident != "Option" {
+        return None;
+    }
+
+    match &seg.arguments {
+        syn::PathArguments::AngleBracketed(args) => match args.args.first() {
+            Some(syn::GenericArgument::Type(inner_ty)) => Some(inner_ty),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// 泛型参数中的类型, 跳过生命周期
+fn generic_type_args(ty: &Type) -> Vec<&Type> {
+    let Type::Path(type_path) = ungroup(ty) else {
+        return Vec::new();
+    };
+
+    match type_path.path.segments.last().map(|seg| &seg.arguments) {
+        Some(syn::PathArguments::AngleBracketed(args)) => args
+            .args
+            .iter()
+            .filter_map(|arg| match arg {
+                syn::GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// 类型路径的最后一段, 用于识别 `Vec`/`String` 等语法糖, 与前面的模块路径无关
+fn last_ident(ty: &Type) -> Option<String> {
+    match ungroup(ty) {
+        Type::Path(type_path) => type_path
+            .path
+            .segments
+            .last()
+            .map(|seg| seg.ident.to_string()),
+        _ => None,
+    }
+}
+
+/// 长度由 len_from/len_prefix 给出的集合的元素类型, 映射的元素为 `(K, V)`
+fn seq_elem_type(ty: &Type) -> Option<Type> {
+    let ident = last_ident(ty)?;
+    let args = generic_type_args(ty);
+
+    if is_dynamic_list_type(&ident) {
+        return args.first().map(|ty| (*ty).clone());
+    }
+
+    if is_map_type(&ident)
+        && let [key, value, ..] = args.as_slice()
+    {
+        return Some(syn::parse_quote! { (#key, #value) });
+    }
+
+    if is_pointer_type(&ident)
+        && let Some(Type::Slice(slice)) = args.first().map(|ty| ungroup(ty))
+    {
+        return Some((*slice.elem).clone());
+    }
+
+    None
+}
+
+/// 数组或序列的元素类型, 不含映射
+fn list_elem_type(ty: &Type) -> Option<Type> {
+    match ungroup(ty) {
+        Type::Array(type_array) => Some((*type_array.elem).clone()),
+        _ if last_ident(ty).is_some_and(|ident| is_map_type(&ident)) => None,
+        _ => seq_elem_type(ty),
+    }
+}
+
+/// `String`/`str` 以及 `Box<str>`/`Arc<str>`/`Cow<str>` 等, 按分隔符编解码
+fn is_str_like_type(ty: &Type) -> bool {
+    let Some(ident) = last_ident(ty) else {
+        return false;
+    };
+
+    is_str_type(&ident)
+        || (is_pointer_type(&ident)
+            && generic_type_args(ty)
+                .first()
+                .is_some_and(|arg| last_ident(arg).is_some_and(|ident| ident == "str")))
+}
+
+/// 字符串或元素 (逐层) 为字符串的数组/序列, 需要按分隔符逐个编码
+///
+/// 带 delimiter 的非整数, 非集合类型 (例如 `String` 的别名) 也按字符串处理
+fn contains_str_type(ty: &Type, field_info: &FieldInfo) -> bool {
+    match list_elem_type(ty) {
+        Some(elem) => contains_str_type(&elem, field_info),
+        None => {
+            is_str_like_type(ty)
+                || (field_info.str_delimiter.is_some()
+                    && !is_fixed_primitive_type(ty)
+                    && seq_elem_type(ty).is_none())
+        }
+    }
+}
+
+/// 解码时前面的字段已经是局部变量, 编码时需要把字段名替换成 `self.field`
+fn self_condition(condition: &syn::Expr, fields: &HashMap<String, FieldInfo>) -> syn::Expr {
+    struct SelfFieldRewriter<'a>(&'a HashMap<String, FieldInfo>);
+
+    impl VisitMut for SelfFieldRewriter<'_> {
+        fn visit_expr_mut(&mut self, expr: &mut syn::Expr) {
+            if let syn::Expr::Path(expr_path) = expr
+                && let Some(ident) = expr_path.path.get_ident()
+                && self.0.contains_key(&ident.to_string())
+            {
+                let ident = ident.clone();
+                *expr = syn::parse_quote! { self.#ident };
+                return;
+            }
+
+            visit_mut::visit_expr_mut(self, expr);
+        }
+    }
+
+    let mut expr = condition.clone();
+    SelfFieldRewriter(fields).visit_expr_mut(&mut expr);
+    expr
+}
 
 #[derive(Debug, Default, Clone)]
 struct EnumInfo {
@@ -128,29 +442,38 @@ struct EnumInfo {
 }
 
 impl EnumInfo {
-    fn parse(input: &syn::DeriveInput) -> Self {
+    fn parse(input: &syn::DeriveInput) -> Result<Self> {
         let mut enum_info = Self::default();
+        let mut errors = Errors::default();
 
         let mut repr_ty = None;
 
         for attr in &input.attrs {
-            if attr.path().is_ident("repr") {
-                if let Meta::List(meta_list) = &attr.meta {
-                    let Ok(meta_list_parser) =
-                        syn::parse2::<MetaListParser>(meta_list.tokens.clone())
-                    else {
-                        continue;
-                    };
-
-                    if let Some(meta) = meta_list_parser.0.first() {
-                        repr_ty = Some(meta.path().get_ident().unwrap().to_string())
-                    }
-                }
+            if attr.path().is_ident("repr")
+                && let Meta::List(meta_list) = &attr.meta
+            {
+                let Ok(meta_list_parser) = syn::parse2::<MetaListParser>(meta_list.tokens.clone())
+                else {
+                    continue;
+                };
+
+                // repr(C, u8) 之类的组合中取整数类型
+                repr_ty = meta_list_parser
+                    .0
+                    .iter()
+                    .filter_map(|meta| meta.path().get_ident().map(Ident::to_string))
+                    .find(|ident| is_fixed_primitive_type_str(ident) && ident != "bool")
+                    .or(repr_ty);
             }
         }
 
         if let syn::Data::Enum(data_enum) = &input.data {
             for var in data_enum.variants.iter() {
+                if !matches!(var.fields, syn::Fields::Unit) {
+                    errors.push(var, "serdeb only supports enums without fields");
+                    continue;
+                }
+
                 let ident = &var.ident;
                 let discr = var
                     .discriminant
@@ -163,83 +486,206 @@ impl EnumInfo {
 
         match repr_ty {
             Some(repr_ty) => enum_info.repr_ty = repr_ty,
-            None => panic!("Enum need repr(u8/u16....)"),
+            None => errors.push(&input.ident, "enum need an integer repr, e.g. #[repr(u8)]"),
         }
 
-        enum_info
+        errors.finish()?;
+        Ok(enum_info)
     }
 }
 
 #[derive(Debug, Clone, Default)]
 struct FieldInfo {
+    /// 不参与编解码, 解码时取默认值
+    skip: bool,
     use_default: bool,
-    default_value: Option<String>,
+    default_value: Option<syn::Expr>,
     bit_width: Option<usize>,
     len_from: Option<String>,
+    /// 长度以该类型写在集合之前, 类型需实现 `LenPrefix`
+    len_prefix: Option<Type>,
+    varint: bool,
+    zigzag: bool,
+    /// 整数只编码低 N 个字节, `bytes = 3`
+    bytes: Option<usize>,
+    /// 16 位字倒序, `word_order = "swapped"`
+    word_swapped: bool,
+    /// 线上传输的类型, `as = u16`
+    wire_ty: Option<Type>,
+    /// 浮点字段按定点数传输时的 (scale, offset)
+    fixed_point: Option<(f64, f64)>,
+    /// 自定义编码函数, `with = module` 时为 `module::encode`
+    encode_with: Option<syn::Path>,
+    /// 自定义解码函数, `with = module` 时为 `module::decode`
+    decode_with: Option<syn::Path>,
+    /// 传给自定义函数的兄弟字段
+    context: Vec<String>,
     str_delimiter: Option<Vec<u8>>,
+    condition: Option<syn::Expr>,
+    since: Option<u32>,
+    until: Option<u32>,
+    computed: Option<ComputedField>,
+    range_expr: Option<String>,
+    /// 计算范围的起止位置变量名, 由 StructInfo::parse 解析 `over`/`len_of` 得到
+    range: Option<[String; 2]>,
+    /// 范围内的字段全部处理完后才能回填/校验, 记录最后一个字段的下标
+    fill_after: Option<usize>,
+}
+
+/// 编码时由其它字段计算得到的字段, 先预留位置, 之后回填
+#[derive(Debug, Clone)]
+enum ComputedField {
+    /// 校验算法的类型路径
+    Checksum(syn::Path),
+    /// 范围内字段的字节长度
+    Length,
 }
 
-#[allow(unused)]
 #[derive(Debug, Default, Clone)]
 struct StructInfo {
     fields: HashMap<String, FieldInfo>,
     bitfield_sections: Vec<([usize; 2], usize)>,
+    has_computed: bool,
+    /// `#[serdeb(bound = "...")]` 指定的 Encode/EncodedSize 约束, 替代自动推断
+    encode_bound: Option<syn::WhereClause>,
+    /// Decode 约束, 同上
+    decode_bound: Option<syn::WhereClause>,
 }
 
 impl StructInfo {
-    fn parse(input: &syn::DeriveInput) -> Self {
+    fn parse(input: &syn::DeriveInput) -> Result<Self> {
         let mut struct_info = Self::default();
+        let mut errors = Errors::default();
+        struct_info.parse_bound(&input.attrs, &mut errors);
 
-        if let syn::Data::Struct(date_struct) = &input.data {
-            if let syn::Fields::Named(fields) = &date_struct.fields {
-                let mut bit_width = 0;
-                let mut bitfield_start = None;
-                for (i, field) in fields.named.iter().enumerate() {
-                    let ident = field.ident.clone().unwrap().to_string();
+        match &input.data {
+            syn::Data::Struct(syn::DataStruct {
+                fields: syn::Fields::Named(_),
+                ..
+            }) => (),
+            syn::Data::Struct(data_struct) => errors.push(
+                &data_struct.fields,
+                "serdeb only supports structs with named fields",
+            ),
+            _ => (),
+        }
 
-                    let field_info = FieldInfo::parse(field);
+        if let syn::Data::Struct(date_struct) = &input.data
+            && let syn::Fields::Named(fields) = &date_struct.fields
+        {
+            let mut bit_width = 0;
+            let mut bitfield_start = None;
+            for (i, field) in fields.named.iter().enumerate() {
+                let ident = field.ident.clone().unwrap().to_string();
 
-                    if let Some(v) = field_info.bit_width {
-                        if bitfield_start.is_none() {
-                            bitfield_start = Some(i);
-                        }
-                        bit_width += v;
-                    } else {
-                        if let Some(bitfield_start) = bitfield_start.take() {
-                            struct_info
-                                .bitfield_sections
-                                .push(([bitfield_start, i], (bit_width + 7) / 8));
-                            bit_width = 0;
-                        }
-                    }
+                let field_info = FieldInfo::parse(field, &mut errors);
 
+                // 跳过的字段不在线上, 不会打断位域段
+                if field_info.skip {
                     struct_info.fields.insert(ident, field_info);
+                    continue;
+                }
+
+                if let Some(v) = field_info.bit_width {
+                    if bitfield_start.is_none() {
+                        bitfield_start = Some(i);
+                    }
+                    bit_width += v;
+                } else {
+                    if let Some(bitfield_start) = bitfield_start.take() {
+                        struct_info
+                            .bitfield_sections
+                            .push(([bitfield_start, i], bit_width.div_ceil(8)));
+                        bit_width = 0;
+                    }
+                }
+
+                struct_info.fields.insert(ident, field_info);
+            }
+
+            if let Some(bitfield_start) = bitfield_start.take() {
+                struct_info
+                    .bitfield_sections
+                    .push(([bitfield_start, fields.named.len()], bit_width.div_ceil(8)));
+            }
+
+            let names: Vec<String> = fields
+                .named
+                .iter()
+                .map(|field| field.ident.as_ref().unwrap().to_string())
+                .collect();
+
+            for (i, name) in names.iter().enumerate() {
+                let field_info = struct_info.fields.get_mut(name).unwrap();
+                if field_info.computed.is_none() {
+                    continue;
                 }
 
-                if let Some(bitfield_start) = bitfield_start.take() {
-                    struct_info
-                        .bitfield_sections
-                        .push(([bitfield_start, fields.named.len()], (bit_width + 7) / 8));
+                let allow_self = matches!(field_info.computed, Some(ComputedField::Length));
+                match Self::parse_range(&names, i, field_info.range_expr.as_deref(), allow_self) {
+                    Ok((range, fill_after)) => {
+                        field_info.range = Some(range);
+                        field_info.fill_after = Some(fill_after);
+                        struct_info.has_computed = true;
+                    }
+                    Err(message) => errors.push(&fields.named[i], message),
+                }
+            }
+
+            let ctx_names: Vec<_> = fields
+                .named
+                .iter()
+                .flat_map(|field| {
+                    let field_info =
+                        &struct_info.fields[&field.ident.as_ref().unwrap().to_string()];
+                    field_info.context.iter().map(move |name| (field, name))
+                })
+                .collect();
+            for (field, name) in ctx_names {
+                if !names.contains(name) {
+                    errors.push(&field.ident, format!("ctx field `{name}` not found"));
+                }
+            }
+
+            for field in &fields.named {
+                let field_info = &struct_info.fields[&field.ident.as_ref().unwrap().to_string()];
+                if let Some(len_from) = &field_info.len_from
+                    && !names.contains(len_from)
+                {
+                    errors.push(
+                        &field.ident,
+                        format!("len_from field `{len_from}` not found"),
+                    );
                 }
             }
         }
 
-        struct_info
+        errors.finish()?;
+        Ok(struct_info)
     }
-}
 
-impl FieldInfo {
-    fn parse(field: &syn::Field) -> Self {
-        let ty_str = field.ty.to_token_stream().to_string();
-        let mut info = Self::default();
-        for attr in &field.attrs {
-            let Some(ident) = attr.path().get_ident() else {
-                continue;
-            };
-
-            let ident_str = ident.to_string();
+    /// `bound = "T: Trait"` 同时用于编码和解码,
+    /// `bound(encode = "...", decode = "...")` 分别指定
+    fn parse_bound(&mut self, attrs: &[syn::Attribute], errors: &mut Errors) {
+        let mut bound_str = |expr: &syn::Expr| match expr {
+            syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(lit),
+                ..
+            }) => match syn::parse_str::<syn::WhereClause>(&format!("where {}", lit.value())) {
+                Ok(where_clause) => Some(where_clause),
+                Err(err) => {
+                    errors.push(lit, format!("invalid bound: {err}"));
+                    None
+                }
+            },
+            _ => {
+                errors.push(expr, "bound need a string, e.g. bound = \"T: Encode\"");
+                None
+            }
+        };
 
-            if ident_str.ne("serdeb") {
+        for attr in attrs {
+            if !attr.path().is_ident("serdeb") {
                 continue;
             }
 
@@ -253,98 +699,874 @@ impl FieldInfo {
             };
 
             for meta in &meta_list_parser.0 {
-                if meta.path().is_ident("len_from") {
-                    match meta {
-                        Meta::NameValue(meta_name_value) => {
-                            if let syn::Expr::Path(expr_path) = &meta_name_value.value {
-                                let ident = expr_path.path.get_ident().unwrap().clone();
-                                info.len_from = Some(ident.to_string());
-                            }
-                        }
-                        Meta::List(meta_list) => {
-                            let Ok(meta_list_parser) =
-                                syn::parse2::<MetaListParser>(meta_list.tokens.clone())
-                            else {
+                if !meta.path().is_ident("bound") {
+                    continue;
+                }
+
+                match meta {
+                    Meta::NameValue(meta_name_value) => {
+                        let bound = bound_str(&meta_name_value.value);
+                        self.encode_bound = bound.clone();
+                        self.decode_bound = bound;
+                    }
+                    Meta::List(meta_list) => {
+                        let Ok(meta_list_parser) =
+                            syn::parse2::<MetaListParser>(meta_list.tokens.clone())
+                        else {
+                            continue;
+                        };
+
+                        for meta in &meta_list_parser.0 {
+                            let Meta::NameValue(meta_name_value) = meta else {
                                 continue;
                             };
-
-                            for meta in &meta_list_parser.0 {
-                                if let Meta::NameValue(meta_name_value) = meta {
-                                    if meta_name_value.path.is_ident("deserialize") {
-                                        if let syn::Expr::Path(expr_path) = &meta_name_value.value {
-                                            let ident = expr_path.path.get_ident().unwrap().clone();
-                                            info.len_from = Some(ident.to_string());
-                                        }
-                                    }
-                                }
+                            if meta_name_value.path.is_ident("encode") {
+                                self.encode_bound = bound_str(&meta_name_value.value);
+                            } else if meta_name_value.path.is_ident("decode") {
+                                self.decode_bound = bound_str(&meta_name_value.value);
                             }
                         }
-                        _ => (),
                     }
+                    _ => (),
                 }
+            }
+        }
+    }
 
-                if meta.path().is_ident("bit_width") {                    
-                    if !is_enum_type(&ty_str) && !is_fixed_primitive_type_str(&ty_str) {
-                        panic!("bit_width only support enum or primitive_type");
-                    }
+    /// impl 使用的泛型, where 子句中加上指定的约束,
+    /// 未指定时为编解码字段中出现的类型参数加上 `trait_path` 约束
+    fn impl_generics(
+        &self,
+        input: &syn::DeriveInput,
+        trait_path: proc_macro2::TokenStream,
+        encode: bool,
+    ) -> syn::Generics {
+        let mut generics = input.generics.clone();
+        let bound = match encode {
+            true => &self.encode_bound,
+            false => &self.decode_bound,
+        };
+
+        let predicates: Vec<syn::WherePredicate> = match bound {
+            Some(where_clause) => where_clause.predicates.iter().cloned().collect(),
+            None => {
+                let syn::Data::Struct(data_struct) = &input.data else {
+                    return generics;
+                };
+
+                let field_tys: Vec<&Type> = data_struct
+                    .fields
+                    .iter()
+                    .filter(|field| {
+                        let field_info = &self.fields[&field.ident.as_ref().unwrap().to_string()];
+                        let custom = match encode {
+                            true => field_info.encode_with.is_some(),
+                            false => field_info.decode_with.is_some(),
+                        };
+                        !field_info.skip
+                            && !custom
+                            && field_info.wire_ty.is_none()
+                            && last_ident(&field.ty).as_deref() != Some("PhantomData")
+                    })
+                    .map(|field| &field.ty)
+                    .collect();
+
+                generics
+                    .type_params()
+                    .filter(|param| field_tys.iter().any(|ty| type_uses_ident(ty, &param.ident)))
+                    .map(|param| {
+                        let ident = &param.ident;
+                        syn::parse_quote! { #ident: #trait_path }
+                    })
+                    .collect()
+            }
+        };
+
+        generics.make_where_clause().predicates.extend(predicates);
+        generics
+    }
+
+    /// 解析 `"start..=end"` / `"start..end"` / `"field"`, 返回起止位置变量名和回填时机
+    ///
+    /// 省略起点表示从结构体开头开始, 省略终点表示到计算字段之前为止。
+    /// 校验和不能覆盖自身, 否则编码时算的是占位的 0, 解码时算的是实际值
+    fn parse_range(
+        names: &[String],
+        index: usize,
+        expr: Option<&str>,
+        allow_self: bool,
+    ) -> std::result::Result<([String; 2], usize), String> {
+        let default_end = format!("__{}_start", names[index]);
 
-                    match meta {
+        let Some(expr) = expr else {
+            return Ok((["__serdeb_start".to_string(), default_end], index));
+        };
+
+        let expr = if expr.contains("..") {
+            expr.to_string()
+        } else {
+            format!("{expr}..={expr}")
+        };
+
+        let range: syn::ExprRange =
+            syn::parse_str(&expr).map_err(|err| format!("invalid field range `{expr}`: {err}"))?;
+
+        let field_index = |expr: &syn::Expr| -> std::result::Result<usize, String> {
+            let ident = expr_ident(expr).ok_or("field range bound must be a field name")?;
+            names
+                .iter()
+                .position(|name| ident == name)
+                .ok_or_else(|| format!("field range field `{ident}` not found"))
+        };
+
+        let start_index = match &range.start {
+            Some(expr) => field_index(expr)?,
+            None => 0,
+        };
+        let start = match &range.start {
+            Some(_) => format!("__{}_start", names[start_index]),
+            None => "__serdeb_start".to_string(),
+        };
+
+        let (end, last, covers_self) = match &range.end {
+            Some(expr) => {
+                let end_index = field_index(expr)?;
+                match range.limits {
+                    syn::RangeLimits::Closed(_) => (
+                        format!("__{}_end", names[end_index]),
+                        end_index,
+                        (start_index..=end_index).contains(&index),
+                    ),
+                    syn::RangeLimits::HalfOpen(_) => (
+                        format!("__{}_start", names[end_index]),
+                        end_index.saturating_sub(1),
+                        (start_index..end_index).contains(&index),
+                    ),
+                }
+            }
+            None => (default_end, index, false),
+        };
+
+        if covers_self && !allow_self {
+            return Err(format!(
+                "checksum range must not cover the checksum field `{}` itself",
+                names[index]
+            ));
+        }
+
+        Ok(([start, end], last.max(index)))
+    }
+
+    /// 下标在 range 内的字段处理完后, 需要回填/校验的计算字段
+    fn computed_fields_after<'a>(
+        &self,
+        fields: &'a syn::FieldsNamed,
+        range: std::ops::Range<usize>,
+    ) -> Vec<(&'a syn::Field, &FieldInfo)> {
+        if !self.has_computed {
+            return Vec::new();
+        }
+
+        fields
+            .named
+            .iter()
+            .filter_map(|field| {
+                let field_info = &self.fields[&field.ident.as_ref().unwrap().to_string()];
+                field_info
+                    .fill_after
+                    .is_some_and(|i| range.contains(&i))
+                    .then_some((field, field_info))
+            })
+            .collect()
+    }
+}
+
+impl FieldInfo {
+    fn parse(field: &syn::Field, errors: &mut Errors) -> Self {
+        let mut info = Self::default();
+        let mut scale = None;
+        let mut offset = None;
+        let mut with = None;
+        // 各属性所在位置, 组合不合法时指向对应的属性
+        let mut spans: HashMap<String, proc_macro2::TokenStream> = HashMap::new();
+        // 属性列表本身没能解析, 不再报告由此引起的缺失属性
+        let mut malformed = false;
+
+        for attr in &field.attrs {
+            if !attr.path().is_ident("serdeb") {
+                continue;
+            }
+
+            let meta_list = match attr.meta.require_list() {
+                Ok(meta_list) => meta_list,
+                Err(err) => {
+                    errors.push_error(err);
+                    malformed = true;
+                    continue;
+                }
+            };
+
+            let meta_list_parser = match syn::parse2::<MetaListParser>(meta_list.tokens.clone()) {
+                Ok(meta_list_parser) => meta_list_parser,
+                Err(err) => {
+                    errors.push_error(err);
+                    malformed = true;
+                    continue;
+                }
+            };
+
+            for meta in &meta_list_parser.0 {
+                let Some(key) = meta.path().get_ident().map(Ident::to_string) else {
+                    errors.push(meta.path(), "unknown serdeb attribute");
+                    continue;
+                };
+                spans.insert(key.clone(), meta.to_token_stream());
+
+                match key.as_str() {
+                    "len_from" => {
+                        let value = match meta {
+                            Meta::NameValue(meta_name_value) => Some(meta_name_value.value.clone()),
+                            Meta::List(_) => Self::parse_deserialize(meta, errors),
+                            Meta::Path(_) => None,
+                        };
+                        match value.as_ref().and_then(expr_ident) {
+                            Some(ident) => info.len_from = Some(ident.to_string()),
+                            None => {
+                                errors.push(meta, "len_from need a field name, e.g. len_from = len")
+                            }
+                        }
+                    }
+                    "varint" => info.varint = Self::parse_flag(meta, errors),
+                    "zigzag" => info.zigzag = Self::parse_flag(meta, errors),
+                    "skip" => info.skip = Self::parse_flag(meta, errors),
+                    "as" => match meta {
                         Meta::NameValue(meta_name_value) => {
-                            let bit_width = parse_int(&meta_name_value.value).unwrap();
-                            info.bit_width = Some(bit_width);
+                            match syn::parse2(meta_name_value.value.to_token_stream()) {
+                                Ok(ty) => info.wire_ty = Some(ty),
+                                Err(_) => errors.push(meta, "as need a type, e.g. as = u16"),
+                            }
                         }
-                        Meta::List(meta_list) => {
-                            let Ok(meta_list_parser) =
-                                syn::parse2::<MetaListParser>(meta_list.tokens.clone())
-                            else {
+                        _ => errors.push(meta, "as need a type, e.g. as = u16"),
+                    },
+                    "with" | "encode_with" | "decode_with" => {
+                        let path = match meta {
+                            Meta::NameValue(syn::MetaNameValue {
+                                value: syn::Expr::Path(expr_path),
+                                ..
+                            }) => expr_path.path.clone(),
+                            _ => {
+                                errors.push(meta, format!("{key} need a path, e.g. {key} = bcd"));
                                 continue;
-                            };
-
-                            for meta in &meta_list_parser.0 {
-                                if let Meta::NameValue(meta_name_value) = meta {
-                                    if meta_name_value.path.is_ident("deserialize") {
-                                        let bit_width = parse_int(&meta_name_value.value).unwrap();
-                                        info.bit_width = Some(bit_width);
-                                    }
-                                }
                             }
+                        };
+
+                        match key.as_str() {
+                            "with" => with = Some(path),
+                            "encode_with" => info.encode_with = Some(path),
+                            _ => info.decode_with = Some(path),
                         }
-                        _ => (),
                     }
-                }
+                    "ctx" => {
+                        let exprs: Vec<&syn::Expr> = match meta {
+                            Meta::NameValue(meta_name_value) => match &meta_name_value.value {
+                                syn::Expr::Tuple(tuple) => tuple.elems.iter().collect(),
+                                syn::Expr::Paren(paren) => vec![&*paren.expr],
+                                expr => vec![expr],
+                            },
+                            _ => Vec::new(),
+                        };
 
-                if meta.path().is_ident("default") {
-                    match meta {
-                        Meta::Path(_) => {
+                        let names: Option<Vec<String>> = exprs
+                            .into_iter()
+                            .map(|expr| expr_ident(expr).map(Ident::to_string))
+                            .collect();
+                        match names {
+                            Some(names) if !names.is_empty() => info.context = names,
+                            _ => errors.push(meta, "ctx need field names, e.g. ctx = (kind, len)"),
+                        }
+                    }
+                    "scale" | "offset" => {
+                        let value = match meta {
+                            Meta::NameValue(meta_name_value) => parse_float(&meta_name_value.value),
+                            _ => None,
+                        };
+                        match (value, key.as_str()) {
+                            (Some(value), "scale") => scale = Some(value),
+                            (Some(value), _) => offset = Some(value),
+                            (None, _) => {
+                                errors.push(meta, format!("{key} need a number, e.g. {key} = 0.01"))
+                            }
+                        }
+                    }
+                    "len_prefix" => match meta {
+                        Meta::NameValue(meta_name_value) => {
+                            match syn::parse2(meta_name_value.value.to_token_stream()) {
+                                Ok(ty) => info.len_prefix = Some(ty),
+                                Err(_) => errors.push(
+                                    meta,
+                                    "len_prefix need an integer type, e.g. len_prefix = u16",
+                                ),
+                            }
+                        }
+                        _ => errors.push(
+                            meta,
+                            "len_prefix need an integer type, e.g. len_prefix = u16",
+                        ),
+                    },
+                    "bit_width" => {
+                        let value = match meta {
+                            Meta::NameValue(meta_name_value) => Some(meta_name_value.value.clone()),
+                            Meta::List(_) => Self::parse_deserialize(meta, errors),
+                            Meta::Path(_) => None,
+                        };
+                        match value.as_ref().and_then(parse_int::<usize>) {
+                            Some(bit_width) if (1..=128).contains(&bit_width) => {
+                                info.bit_width = Some(bit_width)
+                            }
+                            _ => errors.push(
+                                meta,
+                                "bit_width need an integer in 1..=128, e.g. bit_width = 3",
+                            ),
+                        }
+                    }
+                    "bytes" => {
+                        let value = match meta {
+                            Meta::NameValue(meta_name_value) => Some(meta_name_value.value.clone()),
+                            Meta::List(_) => Self::parse_deserialize(meta, errors),
+                            Meta::Path(_) => None,
+                        };
+                        match value.as_ref().and_then(parse_int::<usize>) {
+                            Some(bytes) if (1..=16).contains(&bytes) => info.bytes = Some(bytes),
+                            _ => {
+                                errors.push(meta, "bytes need an integer in 1..=16, e.g. bytes = 3")
+                            }
+                        }
+                    }
+                    "default" => match meta {
+                        Meta::Path(_) => info.use_default = true,
+                        Meta::NameValue(meta_name_value) => {
                             info.use_default = true;
+                            info.default_value = Some(meta_name_value.value.clone());
+                        }
+                        Meta::List(_) => {
+                            errors.push(meta, "default need an expression, e.g. default = 0")
                         }
+                    },
+                    "delimiter" => match meta {
                         Meta::NameValue(meta_name_value) => {
-                            if let syn::Expr::Path(expr_path) = &meta_name_value.value {
-                                let ident = expr_path.path.get_ident().unwrap().clone();
-                                info.use_default = true;
-                                info.default_value = Some(ident.to_string());
+                            match Self::parse_delimiter_expr(&meta_name_value.value) {
+                                Ok(delimiter) => info.str_delimiter = Some(delimiter),
+                                Err(err) => errors.push_error(err),
+                            }
+                        }
+                        _ => errors.push(meta, "delimiter need bytes, e.g. delimiter = b'\\0'"),
+                    },
+                    "since" | "until" => {
+                        let version = match meta {
+                            Meta::NameValue(meta_name_value) => {
+                                parse_int::<u32>(&meta_name_value.value)
                             }
+                            _ => None,
+                        };
+                        match (version, key.as_str()) {
+                            (Some(version), "since") => info.since = Some(version),
+                            (Some(version), _) => info.until = Some(version),
+                            (None, _) => errors
+                                .push(meta, format!("{key} need a u32 version, e.g. {key} = 2")),
                         }
-                        _ => (),
                     }
-                }
-
-                if meta.path().is_ident("delimiter") {
-                    match meta {
+                    "checksum" => match meta {
                         Meta::NameValue(meta_name_value) => {
-                            info.str_delimiter =
-                                Some(Self::parse_delimiter_expr(&meta_name_value.value));
+                            match Self::parse_checksum_expr(&meta_name_value.value) {
+                                Ok(checksum) => {
+                                    info.computed = Some(ComputedField::Checksum(checksum))
+                                }
+                                Err(err) => errors.push_error(err),
+                            }
+                        }
+                        _ => errors.push(
+                            meta,
+                            "checksum need an algorithm, e.g. checksum = \"crc32\"",
+                        ),
+                    },
+                    "over" | "len_of" => {
+                        let Meta::NameValue(syn::MetaNameValue {
+                            value:
+                                syn::Expr::Lit(syn::ExprLit {
+                                    lit: syn::Lit::Str(lit),
+                                    ..
+                                }),
+                            ..
+                        }) = meta
+                        else {
+                            errors.push(
+                                meta,
+                                format!(
+                                    "{key} need a string range, e.g. {key} = \"start..=payload\""
+                                ),
+                            );
+                            continue;
+                        };
+
+                        if key == "len_of" {
+                            info.computed = Some(ComputedField::Length);
+                        }
+                        info.range_expr = Some(lit.value());
+                    }
+                    "word_order" => {
+                        let order = match meta {
+                            Meta::NameValue(syn::MetaNameValue {
+                                value:
+                                    syn::Expr::Lit(syn::ExprLit {
+                                        lit: syn::Lit::Str(lit),
+                                        ..
+                                    }),
+                                ..
+                            }) => Some(lit.value()),
+                            _ => None,
+                        };
+                        match order.as_deref() {
+                            Some("swapped") => info.word_swapped = true,
+                            Some("normal") => info.word_swapped = false,
+                            _ => errors.push(
+                                meta,
+                                "word_order need \"normal\" or \"swapped\", e.g. word_order = \"swapped\"",
+                            ),
+                        }
+                    }
+                    "if" => {
+                        let Meta::NameValue(syn::MetaNameValue {
+                            value:
+                                syn::Expr::Lit(syn::ExprLit {
+                                    lit: syn::Lit::Str(lit),
+                                    ..
+                                }),
+                            ..
+                        }) = meta
+                        else {
+                            errors.push(
+                                meta,
+                                "if need a string expression, e.g. if = \"version >= 2\"",
+                            );
+                            continue;
+                        };
+
+                        match lit.parse::<syn::Expr>() {
+                            Ok(condition) => info.condition = Some(condition),
+                            Err(err) => errors.push(lit, format!("invalid if expression: {err}")),
                         }
-                        _ => (),
                     }
+                    _ => errors.push(meta.path(), format!("unknown serdeb attribute `{key}`")),
                 }
             }
         }
 
+        let mut check = |invalid: bool, keys: &[&str], message: &str| {
+            if invalid {
+                let span = keys
+                    .iter()
+                    .find_map(|key| spans.get(*key))
+                    .cloned()
+                    .unwrap_or_else(|| field.to_token_stream());
+                errors.push(span, message);
+            }
+        };
+
+        let is_float = last_ident(&field.ty).is_some_and(|ident| ident == "f32" || ident == "f64");
+
+        check(
+            info.skip
+                && (info.bit_width.is_some()
+                    || info.computed.is_some()
+                    || info.condition.is_some()
+                    || info.len_from.is_some()
+                    || info.len_prefix.is_some()
+                    || info.wire_ty.is_some()
+                    || info.encode_with.is_some()
+                    || info.decode_with.is_some()
+                    || with.is_some()
+                    || info.varint
+                    || info.bytes.is_some()
+                    || info.word_swapped),
+            &["skip"],
+            "skip can only be used with default",
+        );
+
+        // 其他类型是否能放进位域由 EncodeBits/DecodeBits 决定
+        check(
+            info.bit_width.is_some()
+                && info.wire_ty.is_none()
+                && (is_str_like_type(&field.ty)
+                    || seq_elem_type(&field.ty).is_some()
+                    || matches!(ungroup(&field.ty), Type::Array(_) | Type::Tuple(_))),
+            &["bit_width"],
+            "bit_width is not supported on strings, arrays or collections",
+        );
+
+        check(
+            info.condition.is_some() && info.bit_width.is_some(),
+            &["if"],
+            "if is not supported on bit_width fields",
+        );
+
+        check(
+            (info.since.is_some() || info.until.is_some()) && info.bit_width.is_some(),
+            &["since", "until"],
+            "since/until is not supported on bit_width fields",
+        );
+
+        check(
+            info.computed.is_some() && info.bit_width.is_some(),
+            &["checksum", "len_of"],
+            "checksum/len_of is not supported on bit_width fields",
+        );
+
+        check(
+            info.computed.is_some()
+                && (info.condition.is_some() || info.since.is_some() || info.until.is_some()),
+            &["checksum", "len_of"],
+            "checksum/len_of can not be used with if/since/until",
+        );
+
+        check(
+            matches!(info.computed, Some(ComputedField::Length)) && info.range_expr.is_none(),
+            &["len_of"],
+            "len_of need a field range",
+        );
+
+        check(
+            info.zigzag && !info.varint,
+            &["zigzag"],
+            "zigzag need varint, e.g. #[serdeb(varint, zigzag)]",
+        );
+
+        check(
+            info.varint && (info.bit_width.is_some() || info.computed.is_some()),
+            &["varint"],
+            "varint is not supported on bit_width/checksum/len_of fields",
+        );
+
+        check(
+            info.bytes.is_some()
+                && (info.varint
+                    || info.wire_ty.is_some()
+                    || info.bit_width.is_some()
+                    || info.computed.is_some()),
+            &["bytes"],
+            "bytes is not supported on varint/as/bit_width/checksum/len_of fields",
+        );
+
+        check(
+            info.word_swapped
+                && (info.varint
+                    || info.bytes.is_some()
+                    || info.wire_ty.is_some()
+                    || info.bit_width.is_some()
+                    || info.computed.is_some()),
+            &["word_order"],
+            "word_order is not supported on varint/bytes/as/bit_width/checksum/len_of fields",
+        );
+
+        check(
+            info.len_from.is_some() && info.len_prefix.is_some(),
+            &["len_prefix"],
+            "len_from and len_prefix can not be used together",
+        );
+
+        // 外层的长度只给出元素个数, 内层集合的长度不会写入报文
+        check(
+            seq_elem_type(&field.ty).is_some_and(|elem| seq_elem_type(&elem).is_some()),
+            &["len_from", "len_prefix"],
+            "nested collections need their own length, wrap the inner collection in a struct with len_from or len_prefix",
+        );
+
+        if info.wire_ty.is_some() && is_float {
+            let scale = scale.unwrap_or(1.0);
+            check(scale == 0.0, &["scale"], "scale can not be zero");
+            info.fixed_point = Some((scale, offset.unwrap_or(0.0)));
+        } else {
+            check(
+                scale.is_some() || offset.is_some(),
+                &["scale", "offset"],
+                "scale/offset need a f32/f64 field and a raw integer type, e.g. as = u16",
+            );
+        }
+
+        check(
+            info.wire_ty.is_some() && info.fixed_point.is_none() && info.varint,
+            &["as"],
+            "as is not supported on varint fields",
+        );
+
+        if let Some(with) = with {
+            let function = |name: &str| {
+                let mut path: syn::Path = with.clone();
+                path.segments
+                    .push(Ident::new(name, Span::call_site()).into());
+                path
+            };
+            info.encode_with.get_or_insert_with(|| function("encode"));
+            info.decode_with.get_or_insert_with(|| function("decode"));
+        }
+
+        let custom = info.encode_with.is_some() || info.decode_with.is_some();
+        check(
+            custom
+                && (info.bit_width.is_some()
+                    || info.varint
+                    || info.bytes.is_some()
+                    || info.word_swapped
+                    || info.wire_ty.is_some()
+                    || info.len_prefix.is_some()
+                    || info.computed.is_some()),
+            &["with", "encode_with", "decode_with"],
+            "with is not supported on bit_width/varint/bytes/word_order/as/len_prefix/checksum/len_of fields",
+        );
+
+        // 以下字段按类型展开解码, 条件字段为 Option<T> 时展开的是 T
+        let std_decoded = !info.skip
+            && info.decode_with.is_none()
+            && info.fixed_point.is_none()
+            && info.bit_width.is_none();
+        let decoded_ty = info
+            .condition
+            .as_ref()
+            .and_then(|_| option_inner_type(&field.ty))
+            .unwrap_or(&field.ty);
+        let is_array = matches!(ungroup(decoded_ty), Type::Array(_));
+
+        check(
+            std_decoded && is_array && info.len_prefix.is_some(),
+            &["len_prefix"],
+            "len_prefix is not supported on arrays",
+        );
+
+        check(
+            std_decoded
+                && info.len_prefix.is_some()
+                && info.wire_conversion().is_none()
+                && list_elem_type(decoded_ty).is_none()
+                && contains_str_type(decoded_ty, &info),
+            &["len_prefix"],
+            "len_prefix is not supported on string fields, use delimiter",
+        );
+
+        check(
+            !custom && !info.context.is_empty(),
+            &["ctx"],
+            "ctx need with/encode_with/decode_with",
+        );
+
+        check(
+            info.bit_width.is_some() && is_float && info.fixed_point.is_none(),
+            &["bit_width"],
+            "bit_width on f32/f64 need a raw integer type, e.g. as = u16",
+        );
+
+        if let Some(bit_width) = info.bit_width {
+            let raw_ty = info.wire_ty.as_ref().unwrap_or(&field.ty);
+            if let Some(raw_width) = bits_raw_width(raw_ty) {
+                check(
+                    bit_width > raw_width,
+                    &["bit_width"],
+                    &format!("bit_width = {bit_width} is wider than the {raw_width}-bit raw type"),
+                );
+            }
+        }
+
+        check(
+            info.fixed_point.is_some() && (info.varint || info.computed.is_some()),
+            &["scale", "as"],
+            "scale/offset is not supported on varint/checksum/len_of fields",
+        );
+
+        if let Some(bytes) = info.bytes
+            && leaf_int_size(&field.ty).is_some_and(|size| bytes > size)
+        {
+            errors.push(
+                &field.ty,
+                format!("bytes = {bytes} is wider than the integer type"),
+            );
+        }
+
+        // 定长数组的元素沿用字段的属性; 长度属性写错时已经报过错
+        let mut leaf_ty = decoded_ty;
+        while let Type::Array(type_array) = ungroup(leaf_ty) {
+            leaf_ty = &type_array.elem;
+        }
+        if std_decoded
+            && !malformed
+            && !spans.contains_key("len_from")
+            && !spans.contains_key("len_prefix")
+            && seq_elem_type(leaf_ty).is_some()
+        {
+            errors.push(leaf_ty, "collection field need len_from or len_prefix");
+        }
+
+        // 按线上实际编码的叶子类型校验, `Wrapping<u32>` 等包装类型没有 `WordSwapped` 实现
+        if info.word_swapped
+            && !leaf_ident(&field.ty).is_some_and(|ident| {
+                matches!(
+                    ident.as_str(),
+                    "u32" | "i32" | "f32" | "u64" | "i64" | "f64"
+                )
+            })
+        {
+            errors.push(&field.ty, "word_order need a u32/i32/f32/u64/i64/f64 field");
+        }
+
+        if contains_platform_int_type(&field.ty)
+            && !info.skip
+            && !custom
+            && !info.varint
+            && info.wire_ty.is_none()
+        {
+            errors.push(
+                &field.ty,
+                "usize/isize need an explicit wire type, e.g. as = u32 or varint",
+            );
+        }
+
         info
     }
 
-    fn parse_delimiter_expr(expr: &syn::Expr) -> Vec<u8> {
+    /// 没有值的开关属性, 例如 `varint`
+    fn parse_flag(meta: &Meta, errors: &mut Errors) -> bool {
+        if let Meta::Path(_) = meta {
+            return true;
+        }
+
+        errors.push(
+            meta,
+            format!("{} does not take a value", meta.path().to_token_stream()),
+        );
+        false
+    }
+
+    /// `len_from(deserialize = len)` / `bit_width(deserialize = 3)` 中的值
+    fn parse_deserialize(meta: &Meta, errors: &mut Errors) -> Option<syn::Expr> {
+        let Meta::List(meta_list) = meta else {
+            return None;
+        };
+
+        match syn::parse2::<MetaListParser>(meta_list.tokens.clone()) {
+            Ok(nested) => nested.0.into_iter().find_map(|meta| match meta {
+                Meta::NameValue(meta_name_value)
+                    if meta_name_value.path.is_ident("deserialize") =>
+                {
+                    Some(meta_name_value.value)
+                }
+                _ => None,
+            }),
+            Err(err) => {
+                errors.push_error(err);
+                None
+            }
+        }
+    }
+
+    /// varint/bytes/word_order 字段的标量按此包装类型编解码, 如 `::serdeb::VarInt::<u32>`
+    fn int_wrapper(&self, ty: &Type) -> Option<proc_macro2::TokenStream> {
+        if self.word_swapped {
+            return Some(quote! { ::serdeb::WordSwapped::<#ty> });
+        }
+        match (self.varint, self.zigzag, self.bytes) {
+            (true, false, _) => Some(quote! { ::serdeb::VarInt::<#ty> }),
+            (true, true, _) => Some(quote! { ::serdeb::ZigZag::<#ty> }),
+            (false, _, Some(bytes)) => {
+                let bytes = proc_macro2::Literal::usize_unsuffixed(bytes);
+                Some(quote! { ::serdeb::NarrowInt::<#ty, #bytes> })
+            }
+            (false, _, None) => None,
+        }
+    }
+
+    /// 按 `Into`/`TryFrom` 与线上类型互相转换时的线上类型
+    fn wire_conversion(&self) -> Option<Type> {
+        if self.fixed_point.is_some() {
+            return None;
+        }
+        self.wire_ty.clone()
+    }
+
+    /// 定点数字段的原始类型和 scale/offset 字面量
+    fn fixed_point(&self) -> Option<(Type, proc_macro2::Literal, proc_macro2::Literal)> {
+        let (scale, offset) = self.fixed_point?;
+        let raw_ty = self.wire_ty.clone()?;
+        Some((
+            raw_ty,
+            proc_macro2::Literal::f64_suffixed(scale),
+            proc_macro2::Literal::f64_suffixed(offset),
+        ))
+    }
+
+    /// `default = expr` 的表达式, 未指定时使用 `Default`
+    fn default_expr(&self, ty: &Type) -> proc_macro2::TokenStream {
+        match &self.default_value {
+            Some(expr) => quote! { #expr },
+            None => quote! { <#ty as ::core::default::Default>::default() },
+        }
+    }
+
+    /// 自定义函数的兄弟字段参数, 一个字段时传引用, 多个时传引用元组
+    fn context_arg(
+        &self,
+        fields: impl Fn(&Ident) -> proc_macro2::TokenStream,
+    ) -> proc_macro2::TokenStream {
+        let args: Vec<_> = self
+            .context
+            .iter()
+            .map(|name| fields(&Ident::new(name, Span::call_site())))
+            .collect();
+
+        match args.as_slice() {
+            [] => quote! {},
+            [arg] => quote! { , &#arg },
+            args => quote! { , (#(&#args),*) },
+        }
+    }
+
+    /// 字段所在的版本区间, 按版本编解码时使用
+    fn version_guard(&self) -> Option<proc_macro2::TokenStream> {
+        match (self.since, self.until) {
+            (None, None) => None,
+            (Some(since), None) => Some(quote! { __version >= #since }),
+            (None, Some(until)) => Some(quote! { __version <= #until }),
+            (Some(since), Some(until)) => Some(quote! { (#since..=#until).contains(&__version) }),
+        }
+    }
+
+    /// 内置算法使用字符串名字, 自定义算法使用实现了 Checksum 的类型路径
+    fn parse_checksum_expr(expr: &syn::Expr) -> Result<syn::Path> {
+        match expr {
+            syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(lit),
+                ..
+            }) => {
+                let algorithm = match lit.value().as_str() {
+                    "crc16_modbus" => "Crc16Modbus",
+                    "crc32" => "Crc32",
+                    "xor8" => "Xor8",
+                    "sum8" => "Sum8",
+                    other => {
+                        return Err(syn::Error::new_spanned(
+                            lit,
+                            format!("unsupported checksum algorithm `{other}`"),
+                        ));
+                    }
+                };
+                let algorithm = Ident::new(algorithm, lit.span());
+                Ok(syn::parse_quote! { ::serdeb::checksum::#algorithm })
+            }
+            syn::Expr::Path(expr_path) => Ok(expr_path.path.clone()),
+            other => Err(syn::Error::new_spanned(
+                other,
+                "unsupported checksum expression, use an algorithm name or a Checksum type",
+            )),
+        }
+    }
+
+    fn parse_delimiter_expr(expr: &syn::Expr) -> Result<Vec<u8>> {
         match expr {
             // delimiter = [0x00, 0xFF]
             syn::Expr::Array(arr) => Self::parse_u8_array(arr),
@@ -353,46 +1575,44 @@ impl FieldInfo {
             syn::Expr::Lit(syn::ExprLit {
                 lit: syn::Lit::Byte(byte),
                 ..
-            }) => {
-                vec![byte.value()]
-            }
+            }) => Ok(vec![byte.value()]),
 
             // delimiter = 0x00 / 0 / 255 / 0b...
             syn::Expr::Lit(syn::ExprLit {
                 lit: syn::Lit::Int(int),
                 ..
-            }) => {
-                let v = int
-                    .base10_parse::<u8>()
-                    .expect("delimiter literal must fit in u8");
-                vec![v]
-            }
+            }) => Ok(vec![Self::parse_u8_lit(int)?]),
 
             // 不支持
-            other => {
-                let s = other.to_token_stream().to_string();
-                panic!("Unsupported delimiter expression: {}", s);
-            }
+            other => Err(syn::Error::new_spanned(
+                other,
+                "unsupported delimiter expression, use a byte, an integer or an array of them",
+            )),
         }
     }
 
-    fn parse_u8_array(arr: &syn::ExprArray) -> Vec<u8> {
+    fn parse_u8_array(arr: &syn::ExprArray) -> Result<Vec<u8>> {
         arr.elems
             .iter()
             .map(|elem| match elem {
                 syn::Expr::Lit(syn::ExprLit {
                     lit: syn::Lit::Int(int),
                     ..
-                }) => int.base10_parse::<u8>().expect("delimiter must be u8"),
+                }) => Self::parse_u8_lit(int),
                 syn::Expr::Lit(syn::ExprLit {
                     lit: syn::Lit::Byte(byte),
                     ..
-                }) => byte.value(),
-                other => {
-                    let s = other.to_token_stream().to_string();
-                    panic!("Unsupported delimiter element: {}", s);
-                }
+                }) => Ok(byte.value()),
+                other => Err(syn::Error::new_spanned(
+                    other,
+                    "unsupported delimiter element, use a byte or an integer",
+                )),
             })
             .collect()
     }
+
+    fn parse_u8_lit(int: &syn::LitInt) -> Result<u8> {
+        int.base10_parse::<u8>()
+            .map_err(|_| syn::Error::new_spanned(int, "delimiter byte must fit in u8"))
+    }
 }